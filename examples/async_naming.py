@@ -11,7 +11,7 @@ naming_client = nacos.AsyncNacosNamingClient(client_options)
 
 
 # 自定义服务订阅函数，接受的参数为 `nacos.NacosConfigResponse`
-def subscribe_instances(instances: [nacos.NacosServiceInstance]):
+def subscribe_instances(instances: nacos.NacosServiceInstanceList):
     print(f"subscribe_instances,instances={str(instances)}")
     for ins in instances:
         print(f"subscribe_instances,instances[x].ip={ins.ip}")
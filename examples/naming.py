@@ -17,7 +17,7 @@ service_instance = nacos.NacosServiceInstance("127.0.0.1", 8080)
 
 
 # 自定义服务订阅函数，接受的参数为 `nacos.NacosConfigResponse`
-def subscribe_instances(instances: [nacos.NacosServiceInstance]):
+def subscribe_instances(instances: nacos.NacosServiceInstanceList):
     print(f"subscribe_instances,instances={str(instances)}")
     for ins in instances:
         print(f"subscribe_instances,instances[x].ip={ins.ip}")
@@ -47,4 +47,14 @@ for i in get_instances:
 service_instance2 = nacos.NacosServiceInstance("127.0.0.2", 8080)
 naming_client.batch_register_instance(service_name, group, [service_instance, service_instance2])
 
+time.sleep(1)
+
+# example: 负载均衡器，基于订阅维护的本地实例快照挑选实例，无需网络请求
+# strategy 可选 weighted_random(默认), round_robin, least_recently_used, consistent_hash
+load_balancer = naming_client.load_balancer(service_name, group, strategy="round_robin")
+print(f"load_balancer.select()={str(load_balancer.select())}")
+
+consistent_hash_balancer = naming_client.load_balancer(service_name, group, strategy="consistent_hash")
+print(f"consistent_hash_balancer.select(key)={str(consistent_hash_balancer.select('user-1'))}")
+
 time.sleep(300)
@@ -1,4 +1,50 @@
-from typing import Callable, Dict, List, Optional
+from typing import (
+    Any,
+    Awaitable,
+    Callable,
+    ClassVar,
+    Dict,
+    Iterator,
+    List,
+    Optional,
+    Sequence,
+    Tuple,
+    Union,
+    overload,
+)
+
+TLS_ENABLED: bool
+"""Whether the connections with server are TLS, by the build feature `tls`. The CA file is set by env `NACOS_CLIENT_TLS_CA_CERT`, default the system roots."""
+
+def init_runtime(worker_threads: Optional[int] = None) -> None:
+    """Init the process-wide runtime with worker_threads, default the count of CPU cores. It must be called before building any client, otherwise the runtime is initialized already. In the child process of fork, it could be called again before building any client there."""
+
+    ...
+
+def metrics() -> Dict[str, Any]:
+    """Metrics of all sync and async clients in this process: requests (count, errors by kind and latency histogram per public method of the clients, e.g. get_config_bytes, which may send several requests), listener_callbacks (count, failures and duration histogram of config and naming listeners), active_subscriptions and log_events_dropped (the logs of nacos-sdk dropped since python logging falls behind). The histograms are {"count", "sum", "buckets"} in seconds with cumulative buckets. The child process after fork counts from zero."""
+
+    ...
+
+def metrics_prometheus() -> str:
+    """Metrics of all sync and async clients in this process, as Prometheus text exposition format, e.g. for the `/metrics` endpoint."""
+
+    ...
+
+def enable_log_forwarding() -> None:
+    """Forward the logs of nacos-sdk to python logging, by the loggers `nacos_sdk.config`, `nacos_sdk.naming` and so on. It must be called before building any client, otherwise nacos-sdk installs its own subscriber, which writes the log files in `$HOME/logs/nacos` instead, and it raises RuntimeError. The logs are dropped and counted in `metrics()` when python logging falls behind. If it fails, pay attention to err"""
+
+    ...
+
+def set_log_level(level: str) -> None:
+    """Set the level of the logs of nacos-sdk forwarded to python logging by `enable_log_forwarding`, one of TRACE, DEBUG, INFO, WARN, ERROR, OFF, default from env `NACOS_CLIENT_LOGGER_LEVEL` or INFO. They are logged by the loggers `nacos_sdk.config`, `nacos_sdk.naming` and so on, which filter them again by their levels."""
+
+    ...
+
+def enable_opentelemetry(tracer_provider: Optional[Any] = None) -> None:
+    """Enable the spans of OpenTelemetry for the calls of all clients, e.g. `nacos.get_config`, `nacos.register_instance`, with the attributes nacos.namespace, nacos.data_id, nacos.group and nacos.service_name. They are parented to the current context of OpenTelemetry, and exported by the tracer_provider, default the global one. The listener callbacks run in the spans `nacos.config.notify` and `nacos.naming.notify`, linked to the span of the call adding the listener. It needs the package `opentelemetry-api`."""
+
+    ...
 
 class ClientOptions:
     def __init__(
@@ -15,7 +61,26 @@ class ClientOptions:
         naming_load_cache_at_start: Optional[bool] = None,
         config_load_cache_at_start: Optional[bool] = None,
         endpoint: Optional[str] = None,
+        grpc_port: Optional[int] = None,
+        cache_dir: Optional[str] = None,
+        outlier_failure_threshold: Optional[int] = None,
+        outlier_ejection_time_ms: Optional[int] = None,
+        outlier_max_ejection_percent: Optional[int] = None,
+        registration_reconcile_interval_ms: Optional[int] = None,
+        locality_zone: Optional[str] = None,
+        locality_cluster: Optional[str] = None,
+        locality_min_healthy_percent: Optional[int] = None,
+        config_failover_dir: Optional[str] = None,
+        context_path: Optional[str] = None,
     ) -> None: ...
+    def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool:
+        """Equal by all fields. It is mutable, so it is not hashable."""
+
+        ...
+    __hash__: ClassVar[None]  # type: ignore[assignment]
+    def __copy__(self) -> "ClientOptions": ...
+    def __reduce__(self) -> tuple: ...
 
 class NacosConfigResponse:
     @property
@@ -30,24 +95,116 @@ class NacosConfigResponse:
     def content_type(self) -> str: ...
     @property
     def md5(self) -> str: ...
+    @property
+    def from_failover(self) -> bool: ...
+    @property
+    def last_modified(self) -> Optional[int]:
+        """Last modified time in milliseconds since epoch, None unless got with metadata."""
+
+        ...
+    @property
+    def encrypted_data_key(self) -> Optional[str]: ...
+    @property
+    def beta(self) -> bool: ...
+    @property
+    def tags(self) -> List[str]: ...
+    @property
+    def content_bytes(self) -> bytes:
+        """Content as bytes, decoded from base64 if its content_type is `base64` as published by `publish_config_bytes`, otherwise encoded by utf-8. If it fails, pay attention to err"""
+
+        ...
+    def to_dict(self) -> Dict[str, Any]:
+        """Convert to dict, the keys are the field names."""
+
+        ...
+    @staticmethod
+    def from_dict(d: Dict[str, Any]) -> "NacosConfigResponse":
+        """Build from dict, which is the same as the result of `to_dict()`. All keys are required, except `from_failover` and the metadata: `last_modified`, `encrypted_data_key`, `beta`, `tags`."""
+
+        ...
+    def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+    def __copy__(self) -> "NacosConfigResponse": ...
+    def __reduce__(self) -> tuple: ...
+
+class NacosHealthCheckResult:
+    @property
+    def healthy(self) -> bool: ...
+    @property
+    def latency_ms(self) -> Optional[float]: ...
+    @property
+    def server_addr(self) -> str: ...
+    @property
+    def server_version(self) -> Optional[str]: ...
+    @property
+    def auth_status(self) -> str: ...
+    @property
+    def error(self) -> Optional[str]: ...
+    def to_dict(self) -> Dict[str, Any]:
+        """Convert to dict, the keys are the field names, e.g. for the response of readiness probe."""
+
+        ...
+    def __repr__(self) -> str: ...
 
 class NacosConfigClient:
     def __init__(self, client_options: ClientOptions) -> None: ...
-    def get_config(self, data_id: str, group: str) -> str:
-        """Get config's content. If it fails, pay attention to err"""
+    def get_config(
+        self, data_id: str, group: str, default: Optional[str] = None
+    ) -> str:
+        """Get config's content, or the default when the config is not found. If it fails, pay attention to err"""
+
+        ...
+    def get_config_bytes(self, data_id: str, group: str) -> bytes:
+        """Get config's content as bytes, decoded from base64 if its content_type is `base64` as published by `publish_config_bytes`, otherwise encoded by utf-8. If it fails, pay attention to err"""
+
+        ...
+    def get_config_resp(
+        self, data_id: str, group: str, with_metadata: bool = False
+    ) -> NacosConfigResponse:
+        """Get NacosConfigResponse. It is served from the failover dir of ClientOptions when the server is unavailable. with_metadata: also get last_modified, encrypted_data_key, beta and tags by the open api of server, which the SDK does not expose, default false. If it fails, pay attention to err"""
+
+        ...
+    def get_configs(
+        self, keys: List[Tuple[str, str]], max_concurrency: Optional[int] = None
+    ) -> Dict[Tuple[str, str], Union[NacosConfigResponse, Exception]]:
+        """Get the configs of [(data_id, group), ...] concurrently, at most max_concurrency at a time, default 16. Returns a dict of (data_id, group) to NacosConfigResponse, or the err when it fails. If it fails, pay attention to err"""
 
         ...
-    def get_config_resp(self, data_id: str, group: str) -> NacosConfigResponse:
-        """Get NacosConfigResponse. If it fails, pay attention to err"""
+    def wait_for_config(
+        self, data_id: str, group: str, timeout: float
+    ) -> NacosConfigResponse:
+        """Wait until the config appears, returns its NacosConfigResponse. Raise TimeoutError if it does not appear in timeout seconds. If it fails, pay attention to err"""
 
         ...
     def publish_config(self, data_id: str, group: str, content: str) -> bool:
         """Publish config. If it fails, pay attention to err"""
 
+        ...
+    def publish_config_bytes(self, data_id: str, group: str, content: bytes) -> bool:
+        """Publish bytes as config, e.g. certificates, its content is the base64 of bytes, and its content_type is `base64`. Get it by `get_config_bytes`. If it fails, pay attention to err"""
+
         ...
     def remove_config(self, data_id: str, group: str) -> bool:
         """Remove config. If it fails, pay attention to err"""
 
+        ...
+    def export_configs(
+        self, namespace: str, group: Optional[str] = None, *, path: str
+    ) -> int:
+        """Export the configs of namespace (and group) to a zip, returns the count of them.
+        The zip is compatible with the export of Nacos console, with `.metadata.yml` of type, appName and md5.
+        If it fails, pay attention to err"""
+
+        ...
+    def import_configs(
+        self, path: str, policy: Optional[str] = None
+    ) -> Dict[Tuple[str, str], Union[str, Exception]]:
+        """Import the configs of the zip, which is exported by `export_configs` or Nacos console, into the namespace of this client, with the type and appName of `.metadata.yml`, the one of which md5 does not match fails.
+        policy is what to do when the config exists, skip(default), overwrite, or abort which imports nothing if any config exists.
+        Returns the result of each (data_id, group), one of created, overwritten, skipped, conflict, aborted, or the err when it fails.
+        If it fails, pay attention to err"""
+
         ...
     def add_listener(
         self,
@@ -55,27 +212,88 @@ class NacosConfigClient:
         group: str,
         listener: Callable[[NacosConfigResponse], None],
     ):
-        """Add NacosConfigChangeListener callback func, which listen the config change. If it fails, pay attention to err"""
+        """Add NacosConfigChangeListener callback func, which listen the config change. When the server is unavailable, it is notified at once of the content in failover dir. If it fails, pay attention to err"""
+
+        ...
+    def health_check(
+        self, timeout: Optional[float] = None
+    ) -> NacosHealthCheckResult:
+        """Check the server by a round trip of a request over the connection of this client, so its auth plugin is checked as well, e.g. for the readiness probe. Returns NacosHealthCheckResult with the latency, server address, server version and auth status. The server version is best-effort, by the open api under context_path. timeout is in seconds, default 3. If it fails, pay attention to err"""
+
+        ...
+    @property
+    def connection_state(self) -> str:
+        """State of the connection with server: connecting, connected, disconnected or shutdown. It is observed from the results of the requests of this client, and since it is read or called back, of a probe request every 5s while no request is answered. Only answered ones count as connected."""
+
+        ...
+    @property
+    def server_addr(self) -> Optional[str]:
+        """Address of the server of the gRPC connection while connected, None otherwise. nacos-sdk does not expose which server it connects to, so it is only known when server_addr is a single server."""
+
+        ...
+    def on_connection_change(self, callback: Callable[[str], None]) -> None:
+        """Add the callback of connection state change, which is called with the new state, one of connecting, connected, disconnected or shutdown. Its exception is reported by sys.unraisablehook. If it fails, pay attention to err"""
 
         ...
 
 class AsyncNacosConfigClient:
     def __init__(self, client_options: ClientOptions) -> None: ...
-    async def get_config(self, data_id: str, group: str) -> str:
-        """Get config's content. If it fails, pay attention to err"""
+    async def get_config(
+        self, data_id: str, group: str, default: Optional[str] = None
+    ) -> str:
+        """Get config's content, or the default when the config is not found. If it fails, pay attention to err"""
+
+        ...
+    async def get_config_bytes(self, data_id: str, group: str) -> bytes:
+        """Get config's content as bytes, decoded from base64 if its content_type is `base64` as published by `publish_config_bytes`, otherwise encoded by utf-8. If it fails, pay attention to err"""
+
+        ...
+    async def get_config_resp(
+        self, data_id: str, group: str, with_metadata: bool = False
+    ) -> NacosConfigResponse:
+        """Get NacosConfigResponse. It is served from the failover dir of ClientOptions when the server is unavailable. with_metadata: also get last_modified, encrypted_data_key, beta and tags by the open api of server, which the SDK does not expose, default false. If it fails, pay attention to err"""
+
+        ...
+    async def get_configs(
+        self, keys: List[Tuple[str, str]], max_concurrency: Optional[int] = None
+    ) -> Dict[Tuple[str, str], Union[NacosConfigResponse, Exception]]:
+        """Get the configs of [(data_id, group), ...] concurrently, at most max_concurrency at a time, default 16. Returns a dict of (data_id, group) to NacosConfigResponse, or the err when it fails. If it fails, pay attention to err"""
 
         ...
-    async def get_config_resp(self, data_id: str, group: str) -> NacosConfigResponse:
-        """Get NacosConfigResponse. If it fails, pay attention to err"""
+    async def wait_for_config(
+        self, data_id: str, group: str, timeout: float
+    ) -> NacosConfigResponse:
+        """Wait until the config appears, returns its NacosConfigResponse. Raise TimeoutError if it does not appear in timeout seconds. If it fails, pay attention to err"""
 
         ...
     async def publish_config(self, data_id: str, group: str, content: str) -> bool:
         """Publish config. If it fails, pay attention to err"""
 
+        ...
+    async def publish_config_bytes(self, data_id: str, group: str, content: bytes) -> bool:
+        """Publish bytes as config, e.g. certificates, its content is the base64 of bytes, and its content_type is `base64`. Get it by `get_config_bytes`. If it fails, pay attention to err"""
+
         ...
     async def remove_config(self, data_id: str, group: str) -> bool:
         """Remove config. If it fails, pay attention to err"""
 
+        ...
+    async def export_configs(
+        self, namespace: str, group: Optional[str] = None, *, path: str
+    ) -> int:
+        """Export the configs of namespace (and group) to a zip, returns the count of them.
+        The zip is compatible with the export of Nacos console, with `.metadata.yml` of type, appName and md5.
+        If it fails, pay attention to err"""
+
+        ...
+    async def import_configs(
+        self, path: str, policy: Optional[str] = None
+    ) -> Dict[Tuple[str, str], Union[str, Exception]]:
+        """Import the configs of the zip, which is exported by `export_configs` or Nacos console, into the namespace of this client, with the type and appName of `.metadata.yml`, the one of which md5 does not match fails.
+        policy is what to do when the config exists, skip(default), overwrite, or abort which imports nothing if any config exists.
+        Returns the result of each (data_id, group), one of created, overwritten, skipped, conflict, aborted, or the err when it fails.
+        If it fails, pay attention to err"""
+
         ...
     async def add_listener(
         self,
@@ -83,7 +301,27 @@ class AsyncNacosConfigClient:
         group: str,
         listener: Callable[[NacosConfigResponse], None],
     ):
-        """Add NacosConfigChangeListener callback func, which listen the config change. If it fails, pay attention to err"""
+        """Add NacosConfigChangeListener callback func, which listen the config change. When the server is unavailable, it is notified at once of the content in failover dir. If it fails, pay attention to err"""
+
+        ...
+    async def health_check(
+        self, timeout: Optional[float] = None
+    ) -> NacosHealthCheckResult:
+        """Check the server by a round trip of a request over the connection of this client, so its auth plugin is checked as well, e.g. for the readiness probe. Returns NacosHealthCheckResult with the latency, server address, server version and auth status. The server version is best-effort, by the open api under context_path. timeout is in seconds, default 3. If it fails, pay attention to err"""
+
+        ...
+    @property
+    def connection_state(self) -> str:
+        """State of the connection with server: connecting, connected, disconnected or shutdown. It is observed from the results of the requests of this client, and since it is read or called back, of a probe request every 5s while no request is answered. Only answered ones count as connected."""
+
+        ...
+    @property
+    def server_addr(self) -> Optional[str]:
+        """Address of the server of the gRPC connection while connected, None otherwise. nacos-sdk does not expose which server it connects to, so it is only known when server_addr is a single server."""
+
+        ...
+    def on_connection_change(self, callback: Callable[[str], None]) -> None:
+        """Add the callback of connection state change, which is called with the new state, one of connecting, connected, disconnected or shutdown. Its exception is reported by sys.unraisablehook. If it fails, pay attention to err"""
 
         ...
 
@@ -100,20 +338,148 @@ class NacosServiceInstance:
         service_name: Optional[str] = None,
         metadata: Optional[Dict[str, str]] = None,
     ) -> None: ...
+    def to_dict(self) -> Dict[str, Any]:
+        """Convert to dict, the keys are the field names."""
+
+        ...
+    @staticmethod
+    def from_dict(d: Dict[str, Any]) -> "NacosServiceInstance":
+        """Build from dict, which is the same as the result of `to_dict()`. `ip` and `port` are required."""
+
+        ...
+    def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool:
+        """Equal by all fields. It is mutable, so it is not hashable."""
+
+        ...
+    __hash__: ClassVar[None]  # type: ignore[assignment]
+    def __copy__(self) -> "NacosServiceInstance": ...
+    def __reduce__(self) -> tuple: ...
+
+class NacosServiceInstanceList(Sequence[NacosServiceInstance]):
+    """Read-only sequence of NacosServiceInstance, backed by the instances of Rust.
+    The NacosServiceInstance is created only on index or iteration, which is much cheaper than
+    converting all of a service with thousands of instances. Use `to_list()` for a list."""
+
+    def __init__(self, service_instances: List[NacosServiceInstance]) -> None: ...
+    def to_list(self) -> List[NacosServiceInstance]:
+        """Convert all to a list of NacosServiceInstance."""
+
+        ...
+    def __len__(self) -> int: ...
+    @overload
+    def __getitem__(self, index: int) -> NacosServiceInstance: ...
+    @overload
+    def __getitem__(self, index: slice) -> List[NacosServiceInstance]: ...
+    def __iter__(self) -> Iterator[NacosServiceInstance]: ...
+    def __reversed__(self) -> Iterator[NacosServiceInstance]: ...
+    def __contains__(self, value: object) -> bool:
+        """Whether the instance of the same ip, port and cluster_name is in it, the other fields are not compared."""
+
+        ...
+    def index(self, value: object, start: int = 0, stop: Optional[int] = None) -> int:
+        """The first index of the instance of the same ip, port and cluster_name in [start, stop), negative index is supported as list."""
+
+        ...
+    def count(self, value: object) -> int:
+        """Count of the instances of the same ip, port and cluster_name."""
+
+        ...
+    def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __reduce__(self) -> tuple: ...
+
+class LoadBalancer:
+    @property
+    def service_name(self) -> str: ...
+    @property
+    def group(self) -> str: ...
+    @property
+    def clusters(self) -> List[str]: ...
+    @property
+    def strategy(self) -> str:
+        """One of weighted_random, round_robin, least_recently_used, consistent_hash"""
+
+        ...
+    def select(self, key: Optional[str] = None) -> NacosServiceInstance:
+        """Pick one healthy instance from the local snapshot, `key` is required by consistent_hash. If it fails, pay attention to err"""
+
+        ...
+    def instances(self) -> NacosServiceInstanceList:
+        """All instances of the local snapshot, whether healthy or not. If it fails, pay attention to err"""
+
+        ...
+    def close(self) -> None:
+        """Close it, the subscription of its snapshot is removed when the last LoadBalancer of the same service, group and clusters is closed. It is closed when garbage collected too. `select` and `instances` raise after it."""
+
+        ...
+
+class RegistrationManager:
+    """Manager of the instances registered through a naming client. The methods return awaitable when it is from AsyncNacosNamingClient."""
+
+    def instances(self) -> List[Tuple[str, str, NacosServiceInstance]]:
+        """All the registered instances, as tuple (service_name, group, NacosServiceInstance)."""
+
+        ...
+    def reconcile(self) -> Union[int, Awaitable[int]]:
+        """Register again the instances which are missing in server, returns the count of them. If it fails, pay attention to err"""
+
+        ...
+    def update_metadata(
+        self,
+        service_name: str,
+        group: str,
+        service_instance: NacosServiceInstance,
+        metadata: Dict[str, str],
+    ) -> Union[None, Awaitable[None]]:
+        """Merge the metadata into the registered instance, and re-register it. If it fails, pay attention to err"""
+
+        ...
+    def set_weight(
+        self,
+        service_name: str,
+        group: str,
+        service_instance: NacosServiceInstance,
+        weight: float,
+    ) -> Union[None, Awaitable[None]]:
+        """Set the weight of the registered instance, and re-register it. If it fails, pay attention to err"""
+
+        ...
+    def set_enabled(
+        self,
+        service_name: str,
+        group: str,
+        service_instance: NacosServiceInstance,
+        enabled: bool,
+    ) -> Union[None, Awaitable[None]]:
+        """Set the registered instance enabled or not, and re-register it. If it fails, pay attention to err"""
+
+        ...
+    def deregister_all(self) -> Union[None, Awaitable[None]]:
+        """Deregister all the registered instances. If it fails, pay attention to err"""
+
+        ...
 
 class NacosNamingClient:
     def __init__(self, client_options: ClientOptions) -> None: ...
     def register_instance(
-        self, service_name: str, group: str, service_instance: NacosServiceInstance
+        self,
+        service_name: str,
+        group: str,
+        service_instance: NacosServiceInstance,
+        health_check: Optional[Callable[[], bool]] = None,
+        health_check_interval_ms: Optional[int] = None,
+        health_check_timeout_ms: Optional[int] = None,
+        health_check_failure_action: Optional[str] = None,
     ):
-        """Register instance. If it fails, pay attention to err"""
+        """Register instance, with an optional health_check func which returns truthy when healthy. It is called every health_check_interval_ms (default 5000), and it fails if not returned in health_check_timeout_ms (default the interval), e.g. the app is deadlocked. On failure the instance is re-registered with healthy=false or deregistered by health_check_failure_action (unhealthy(default), deregister), and it is restored when the check passes again. The other instances of the service registered by this client are kept. If it fails, pay attention to err"""
 
         ...
 
     def deregister_instance(
         self, service_name: str, group: str, service_instance: NacosServiceInstance
     ):
-        """Deregister instance. If it fails, pay attention to err"""
+        """Deregister instance, the other instances of the service registered by this client are kept. If it fails, pay attention to err"""
 
         ...
 
@@ -123,7 +489,36 @@ class NacosNamingClient:
         group: str,
         service_instances: List[NacosServiceInstance],
     ):
-        """Batch register instance, improve interaction efficiency. If it fails, pay attention to err"""
+        """Batch register instance, improve interaction efficiency. It replaces the instances of the service registered by this client. If it fails, pay attention to err"""
+
+        ...
+
+    def batch_deregister_instance(
+        self,
+        service_name: str,
+        group: str,
+        service_instances: List[NacosServiceInstance],
+    ) -> List[Optional[Exception]]:
+        """Batch deregister instances of the service, the remaining instances of the service registered by this client are published together.
+        Returns the result of each instance, None on success, otherwise the err, which is ValueError for the instance not registered by this client."""
+
+        ...
+
+    def register_many(
+        self, registrations: List[Tuple[str, str, NacosServiceInstance]]
+    ) -> List[Optional[Exception]]:
+        """Register instances of several services, each item is (service_name, group, service_instance), the ones of a service are published together, it continues on error of a service.
+        Returns the result of each item, None on success, otherwise the err of its service."""
+
+        ...
+
+    def update_instance(
+        self, service_name: str, group: str, service_instance: NacosServiceInstance
+    ) -> None:
+        """Update the registered instance in place, e.g. weight, metadata, or enabled=False / weight=0 to drain traffic.
+        It re-registers without deregister, so the consumers see no flap.
+        It raises ValueError when the instance is not registered by this client or does not exist in server.
+        If it fails, pay attention to err"""
 
         ...
 
@@ -133,8 +528,11 @@ class NacosNamingClient:
         group: str,
         clusters: Optional[List[str]] = None,
         subscribe: Optional[bool] = None,
-    ) -> List[NacosServiceInstance]:
-        """Get all instances by service and group. default cluster=[], subscribe=true. If it fails, pay attention to err"""
+        metadata_selector: Optional[Union[Dict[str, str], str]] = None,
+    ) -> NacosServiceInstanceList:
+        """Get all instances by service and group. default cluster=[], subscribe=true.
+        metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
+        If it fails, pay attention to err"""
 
         ...
 
@@ -145,8 +543,11 @@ class NacosNamingClient:
         clusters: Optional[List[str]] = None,
         subscribe: Optional[bool] = None,
         healthy: Optional[bool] = None,
-    ) -> List[NacosServiceInstance]:
-        """Select instances whether healthy or not. default cluster=[], subscribe=true, healthy=true. If it fails, pay attention to err"""
+        metadata_selector: Optional[Union[Dict[str, str], str]] = None,
+    ) -> NacosServiceInstanceList:
+        """Select instances whether healthy or not. default cluster=[], subscribe=true, healthy=true.
+        metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
+        If it fails, pay attention to err"""
 
         ...
 
@@ -156,8 +557,15 @@ class NacosNamingClient:
         group: str,
         clusters: Optional[List[str]] = None,
         subscribe: Optional[bool] = None,
+        metadata_selector: Optional[Union[Dict[str, str], str]] = None,
+        locality_zone: Optional[str] = None,
+        locality_cluster: Optional[str] = None,
     ) -> NacosServiceInstance:
-        """Select one healthy instance. default cluster=[], subscribe=true. If it fails, pay attention to err"""
+        """Select one healthy instance. default cluster=[], subscribe=true.
+        metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
+        It prefers the instances in the same zone or cluster, by locality_zone and locality_cluster of the args or ClientOptions,
+        and falls back to all the zones when the healthy local instances are below locality_min_healthy_percent.
+        If it fails, pay attention to err"""
 
         ...
 
@@ -166,25 +574,85 @@ class NacosNamingClient:
         service_name: str,
         group: str,
         clusters: Optional[List[str]],
-        listener: Callable[[NacosServiceInstance], None],
+        listener: Callable[[NacosServiceInstanceList], None],
     ) -> NacosServiceInstance:
         """Add NacosNamingEventListener callback func, which listen the instance change. If it fails, pay attention to err"""
 
         ...
 
+    def load_balancer(
+        self,
+        service_name: str,
+        group: str,
+        clusters: Optional[List[str]] = None,
+        strategy: Optional[str] = None,
+    ) -> LoadBalancer:
+        """Build a LoadBalancer of service, which keeps a local instance snapshot updated through subscribe, shared by the LoadBalancers of the same service, group and clusters until all of them are closed. strategy: weighted_random(default), round_robin, least_recently_used, consistent_hash. If it fails, pay attention to err"""
+
+        ...
+
+    def report_success(self, service_instance: NacosServiceInstance) -> None:
+        """Report a successful call to the instance, which resets its consecutive failures."""
+
+        ...
+
+    def report_failure(self, service_instance: NacosServiceInstance) -> None:
+        """Report a failed call to the instance, it will be ejected from the select results temporarily when the consecutive failures exceed the threshold."""
+
+        ...
+
+    @property
+    def registration_manager(self) -> RegistrationManager:
+        """Registration manager of the instances registered through this client."""
+
+        ...
+
+    def close(self) -> None:
+        """Close the client, deregister all the instances registered through it. If it fails, pay attention to err"""
+
+        ...
+    def health_check(
+        self, timeout: Optional[float] = None
+    ) -> NacosHealthCheckResult:
+        """Check the server by a round trip of a request over the connection of this client, so its auth plugin is checked as well, e.g. for the readiness probe. Returns NacosHealthCheckResult with the latency, server address, server version and auth status. The server version is best-effort, by the open api under context_path. timeout is in seconds, default 3. If it fails, pay attention to err"""
+
+        ...
+    @property
+    def connection_state(self) -> str:
+        """State of the connection with server: connecting, connected, disconnected or shutdown. It is observed from the results of the requests of this client, and since it is read or called back, of a probe request every 5s while no request is answered. Only answered ones count as connected."""
+
+        ...
+    @property
+    def server_addr(self) -> Optional[str]:
+        """Address of the server of the gRPC connection while connected, None otherwise. nacos-sdk does not expose which server it connects to, so it is only known when server_addr is a single server."""
+
+        ...
+    def on_connection_change(self, callback: Callable[[str], None]) -> None:
+        """Add the callback of connection state change, which is called with the new state, one of connecting, connected, disconnected or shutdown. Its exception is reported by sys.unraisablehook. If it fails, pay attention to err"""
+
+        ...
+
+
 class AsyncNacosNamingClient:
     def __init__(self, client_options: ClientOptions) -> None: ...
     async def register_instance(
-        self, service_name: str, group: str, service_instance: NacosServiceInstance
+        self,
+        service_name: str,
+        group: str,
+        service_instance: NacosServiceInstance,
+        health_check: Optional[Callable[[], bool]] = None,
+        health_check_interval_ms: Optional[int] = None,
+        health_check_timeout_ms: Optional[int] = None,
+        health_check_failure_action: Optional[str] = None,
     ):
-        """Register instance. If it fails, pay attention to err"""
+        """Register instance, with an optional health_check func which returns truthy when healthy. It is called every health_check_interval_ms (default 5000), and it fails if not returned in health_check_timeout_ms (default the interval), e.g. the app is deadlocked. On failure the instance is re-registered with healthy=false or deregistered by health_check_failure_action (unhealthy(default), deregister), and it is restored when the check passes again. The other instances of the service registered by this client are kept. If it fails, pay attention to err"""
 
         ...
 
     async def deregister_instance(
         self, service_name: str, group: str, service_instance: NacosServiceInstance
     ):
-        """Deregister instance. If it fails, pay attention to err"""
+        """Deregister instance, the other instances of the service registered by this client are kept. If it fails, pay attention to err"""
 
         ...
 
@@ -194,7 +662,36 @@ class AsyncNacosNamingClient:
         group: str,
         service_instances: List[NacosServiceInstance],
     ):
-        """Batch register instance, improve interaction efficiency. If it fails, pay attention to err"""
+        """Batch register instance, improve interaction efficiency. It replaces the instances of the service registered by this client. If it fails, pay attention to err"""
+
+        ...
+
+    async def batch_deregister_instance(
+        self,
+        service_name: str,
+        group: str,
+        service_instances: List[NacosServiceInstance],
+    ) -> List[Optional[Exception]]:
+        """Batch deregister instances of the service, the remaining instances of the service registered by this client are published together.
+        Returns the result of each instance, None on success, otherwise the err, which is ValueError for the instance not registered by this client."""
+
+        ...
+
+    async def register_many(
+        self, registrations: List[Tuple[str, str, NacosServiceInstance]]
+    ) -> List[Optional[Exception]]:
+        """Register instances of several services, each item is (service_name, group, service_instance), the ones of a service are published together, it continues on error of a service.
+        Returns the result of each item, None on success, otherwise the err of its service."""
+
+        ...
+
+    async def update_instance(
+        self, service_name: str, group: str, service_instance: NacosServiceInstance
+    ) -> None:
+        """Update the registered instance in place, e.g. weight, metadata, or enabled=False / weight=0 to drain traffic.
+        It re-registers without deregister, so the consumers see no flap.
+        It raises ValueError when the instance is not registered by this client or does not exist in server.
+        If it fails, pay attention to err"""
 
         ...
 
@@ -204,8 +701,11 @@ class AsyncNacosNamingClient:
         group: str,
         clusters: Optional[List[str]] = None,
         subscribe: Optional[bool] = None,
-    ) -> List[NacosServiceInstance]:
-        """Get all instances by service and group. default cluster=[], subscribe=true. If it fails, pay attention to err"""
+        metadata_selector: Optional[Union[Dict[str, str], str]] = None,
+    ) -> NacosServiceInstanceList:
+        """Get all instances by service and group. default cluster=[], subscribe=true.
+        metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
+        If it fails, pay attention to err"""
 
         ...
 
@@ -216,8 +716,11 @@ class AsyncNacosNamingClient:
         clusters: Optional[List[str]] = None,
         subscribe: Optional[bool] = None,
         healthy: Optional[bool] = None,
-    ) -> List[NacosServiceInstance]:
-        """Select instances whether healthy or not. default cluster=[], subscribe=true, healthy=true. If it fails, pay attention to err"""
+        metadata_selector: Optional[Union[Dict[str, str], str]] = None,
+    ) -> NacosServiceInstanceList:
+        """Select instances whether healthy or not. default cluster=[], subscribe=true, healthy=true.
+        metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
+        If it fails, pay attention to err"""
 
         ...
 
@@ -227,8 +730,15 @@ class AsyncNacosNamingClient:
         group: str,
         clusters: Optional[List[str]] = None,
         subscribe: Optional[bool] = None,
+        metadata_selector: Optional[Union[Dict[str, str], str]] = None,
+        locality_zone: Optional[str] = None,
+        locality_cluster: Optional[str] = None,
     ) -> NacosServiceInstance:
-        """Select one healthy instance. default cluster=[], subscribe=true. If it fails, pay attention to err"""
+        """Select one healthy instance. default cluster=[], subscribe=true.
+        metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
+        It prefers the instances in the same zone or cluster, by locality_zone and locality_cluster of the args or ClientOptions,
+        and falls back to all the zones when the healthy local instances are below locality_min_healthy_percent.
+        If it fails, pay attention to err"""
 
         ...
 
@@ -237,8 +747,60 @@ class AsyncNacosNamingClient:
         service_name: str,
         group: str,
         clusters: Optional[List[str]],
-        listener: Callable[[List[NacosServiceInstance]], None],
+        listener: Callable[[NacosServiceInstanceList], None],
     ) -> NacosServiceInstance:
         """Add NacosNamingEventListener callback func, which listen the instance change. If it fails, pay attention to err"""
 
         ...
+
+    async def load_balancer(
+        self,
+        service_name: str,
+        group: str,
+        clusters: Optional[List[str]] = None,
+        strategy: Optional[str] = None,
+    ) -> LoadBalancer:
+        """Build a LoadBalancer of service, which keeps a local instance snapshot updated through subscribe, shared by the LoadBalancers of the same service, group and clusters until all of them are closed. strategy: weighted_random(default), round_robin, least_recently_used, consistent_hash. If it fails, pay attention to err"""
+
+        ...
+
+    def report_success(self, service_instance: NacosServiceInstance) -> None:
+        """Report a successful call to the instance, which resets its consecutive failures."""
+
+        ...
+
+    def report_failure(self, service_instance: NacosServiceInstance) -> None:
+        """Report a failed call to the instance, it will be ejected from the select results temporarily when the consecutive failures exceed the threshold."""
+
+        ...
+
+    @property
+    def registration_manager(self) -> RegistrationManager:
+        """Registration manager of the instances registered through this client."""
+
+        ...
+
+    async def close(self) -> None:
+        """Close the client, deregister all the instances registered through it. If it fails, pay attention to err"""
+
+        ...
+    async def health_check(
+        self, timeout: Optional[float] = None
+    ) -> NacosHealthCheckResult:
+        """Check the server by a round trip of a request over the connection of this client, so its auth plugin is checked as well, e.g. for the readiness probe. Returns NacosHealthCheckResult with the latency, server address, server version and auth status. The server version is best-effort, by the open api under context_path. timeout is in seconds, default 3. If it fails, pay attention to err"""
+
+        ...
+    @property
+    def connection_state(self) -> str:
+        """State of the connection with server: connecting, connected, disconnected or shutdown. It is observed from the results of the requests of this client, and since it is read or called back, of a probe request every 5s while no request is answered. Only answered ones count as connected."""
+
+        ...
+    @property
+    def server_addr(self) -> Optional[str]:
+        """Address of the server of the gRPC connection while connected, None otherwise. nacos-sdk does not expose which server it connects to, so it is only known when server_addr is a single server."""
+
+        ...
+    def on_connection_change(self, callback: Callable[[str], None]) -> None:
+        """Add the callback of connection state change, which is called with the new state, one of connecting, connected, disconnected or shutdown. Its exception is reported by sys.unraisablehook. If it fails, pay attention to err"""
+
+        ...
@@ -1,111 +1,207 @@
 #![deny(clippy::all)]
 
+use crate::future_into_py;
 use pyo3::exceptions::{PyRuntimeError, PyValueError};
-use pyo3::types::PyAnyMethods;
+use pyo3::types::{PyAnyMethods, PyBytes};
 use pyo3::{Bound, PyAny, PyErr, PyResult, Python, pyclass, pymethods};
-use pyo3_async_runtimes::tokio::future_into_py;
 
 use std::sync::Arc;
 
-use crate::config::{NacosConfigChangeListener, transfer_conf_resp};
+use crate::config::{
+    BINARY_CONTENT_TYPE, NacosConfigChangeListener, encode_bytes, fill_metadata, get_configs,
+    into_py_dict, wait_for_config,
+};
+use crate::config_transfer::{ConfigExporter, ImportPolicy, import_configs};
+use crate::connection::{ConnectionTracker, DEFAULT_HEALTH_CHECK_TIMEOUT};
+use crate::failover::ConfigFailover;
+use crate::open_api::OpenApi;
+use crate::otel::ClientTracer;
 
 /// Async Client api of Nacos Config.
 #[pyclass(module = "nacos_sdk_rust_binding_py")]
 pub struct AsyncNacosConfigClient {
     inner: nacos_sdk::api::config::ConfigService,
+    exporter: Arc<ConfigExporter>,
+    failover: Arc<ConfigFailover>,
+    connection: Arc<ConnectionTracker>,
+    open_api: Arc<OpenApi>,
+    tracer: ClientTracer,
+    built_in: crate::fork::BuiltIn,
 }
 
 #[pymethods]
 impl AsyncNacosConfigClient {
     /// Build a Config Client.
     #[new]
-    pub fn new(client_options: crate::ClientOptions) -> PyResult<Self> {
-        let props = nacos_sdk::api::props::ClientProps::new()
-            .server_addr(client_options.server_addr)
-            .namespace(client_options.namespace)
-            .app_name(
-                client_options
-                    .app_name
-                    .unwrap_or(nacos_sdk::api::constants::UNKNOWN.to_string()),
-            )
-            .config_load_cache_at_start(client_options.config_load_cache_at_start.unwrap_or(false));
-
-        let props = if let Some(ep) = client_options.endpoint {
-            props.endpoint(ep)
-        } else {
-            props
-        };
-
-        // need enable_auth_plugin_http with username & password
-        let is_enable_auth_http =
-            client_options.username.is_some() && client_options.password.is_some();
-        // need enable_auth_plugin_aliyun with access_key & access_secret
-        let is_enable_auth_aliyun =
-            client_options.access_key.is_some() && client_options.access_secret.is_some();
-
-        let props = if is_enable_auth_http {
-            props
-                .auth_username(client_options.username.unwrap())
-                .auth_password(client_options.password.unwrap())
-        } else if is_enable_auth_aliyun {
-            props
-                .auth_access_key(client_options.access_key.unwrap())
-                .auth_access_secret(client_options.access_secret.unwrap())
-                .auth_signature_region_id(client_options.signature_region_id.unwrap())
-        } else {
-            props
-        };
+    pub fn new(py: Python<'_>, client_options: crate::ClientOptions) -> PyResult<Self> {
+        let open_api = Arc::new(OpenApi::new(&client_options)?);
+        let tracer = ClientTracer::new(&client_options);
+        let exporter = Arc::new(ConfigExporter::new(open_api.clone()));
+        let connection = Arc::new(ConnectionTracker::new(&client_options));
+        let failover = Arc::new(ConfigFailover::new(&client_options, connection.clone()));
 
-        let config_service_builder = if is_enable_auth_http {
-            nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_http()
-        } else if is_enable_auth_aliyun {
-            nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_aliyun()
-        } else {
-            nacos_sdk::api::config::ConfigServiceBuilder::new(props)
+        let (props, auth_plugin) = client_options.build_client_props()?;
+        let config_service_builder = match auth_plugin {
+            crate::AuthPlugin::Http => {
+                nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_http()
+            }
+            crate::AuthPlugin::Aliyun => {
+                nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_aliyun()
+            }
+            crate::AuthPlugin::None => nacos_sdk::api::config::ConfigServiceBuilder::new(props),
         };
 
-        let config_service = crate::block_on(config_service_builder.build())
+        let config_service = py
+            .detach(|| crate::block_on(config_service_builder.build()))
             .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        connection.set_probe(crate::connection::config_probe(config_service.clone()));
 
         Ok(Self {
             inner: config_service,
+            exporter,
+            failover,
+            connection,
+            open_api,
+            tracer,
+            built_in: crate::fork::BuiltIn::current(),
         })
     }
 
-    /// Get config's content.
+    /// Get config's content, or the default when the config is not found.
     /// If it fails, pay attention to err
+    #[pyo3(signature = (data_id, group, default=None))]
     pub fn get_config<'p>(
         &self,
         py: Python<'p>,
         data_id: String,
         group: String,
+        default: Option<String>,
     ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.get_config",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
         let this = self.inner.clone();
-        future_into_py(py, async move {
-            let config_resp = this
-                .get_config(data_id, group)
-                .await
-                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
-            Ok(transfer_conf_resp(config_resp).content)
-        })
+        let failover = self.failover.clone();
+        future_into_py(
+            py,
+            span.instrument(
+                async move { failover.get_config_or(&this, data_id, group, default).await },
+            ),
+        )
+    }
+
+    /// Get config's content as bytes, decoded from base64 if its content_type is `base64`
+    /// as published by `publish_config_bytes`, otherwise encoded by utf-8.
+    /// If it fails, pay attention to err
+    pub fn get_config_bytes<'p>(
+        &self,
+        py: Python<'p>,
+        data_id: String,
+        group: String,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.get_config_bytes",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
+        let this = self.inner.clone();
+        let failover = self.failover.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                let bytes = failover
+                    .get_config("get_config_bytes", &this, data_id, group)
+                    .await?
+                    .bytes()?;
+                Ok(Python::attach(|py| PyBytes::new(py, &bytes).unbind()))
+            }),
+        )
     }
 
     /// Get NacosConfigResponse.
+    /// It is served from the failover dir of ClientOptions when the server is unavailable.
+    /// with_metadata: also get last_modified, encrypted_data_key, beta and tags by the open api
+    /// of server, which the SDK does not expose, default false.
     /// If it fails, pay attention to err
+    #[pyo3(signature = (data_id, group, with_metadata=false))]
     pub fn get_config_resp<'p>(
         &self,
         py: Python<'p>,
         data_id: String,
         group: String,
+        with_metadata: bool,
     ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.get_config_resp",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
         let this = self.inner.clone();
-        future_into_py(py, async move {
-            let config_resp = this
-                .get_config(data_id, group)
-                .await
-                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
-            Ok(transfer_conf_resp(config_resp))
-        })
+        let failover = self.failover.clone();
+        let open_api = self.open_api.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                let mut config_resp = failover
+                    .get_config("get_config_resp", &this, data_id, group)
+                    .await?;
+                if with_metadata && !config_resp.from_failover {
+                    fill_metadata(&open_api, &mut config_resp).await?;
+                }
+                Ok(config_resp)
+            }),
+        )
+    }
+
+    /// Get the configs of [(data_id, group), ...] concurrently, at most max_concurrency at a time,
+    /// default 16. Returns a dict of (data_id, group) to NacosConfigResponse, or the err when it fails.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (keys, max_concurrency=None))]
+    pub fn get_configs<'p>(
+        &self,
+        py: Python<'p>,
+        keys: Vec<(String, String)>,
+        max_concurrency: Option<usize>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span("nacos.get_configs", &[]);
+        let this = self.inner.clone();
+        let failover = self.failover.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                let results = get_configs(this, failover, keys, max_concurrency).await?;
+                Python::attach(|py| into_py_dict(py, results).map(Bound::unbind))
+            }),
+        )
+    }
+
+    /// Wait until the config appears, returns its NacosConfigResponse.
+    /// Raise TimeoutError if it does not appear in timeout seconds.
+    /// If it fails, pay attention to err
+    pub fn wait_for_config<'p>(
+        &self,
+        py: Python<'p>,
+        data_id: String,
+        group: String,
+        timeout: f64,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.wait_for_config",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
+        let timeout = crate::parse_timeout(timeout)?;
+        let this = self.inner.clone();
+        let failover = self.failover.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                wait_for_config(this, failover, data_id, group, timeout).await
+            }),
+        )
     }
 
     /// Publish config.
@@ -117,12 +213,62 @@ impl AsyncNacosConfigClient {
         group: String,
         content: String,
     ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.publish_config",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
         let this = self.inner.clone();
-        future_into_py(py, async move {
-            this.publish_config(data_id, group, content, None)
-                .await
-                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
-        })
+        let connection = self.connection.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                connection
+                    .track(
+                        "publish_config",
+                        this.publish_config(data_id, group, content, None),
+                    )
+                    .await
+                    .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+            }),
+        )
+    }
+
+    /// Publish bytes as config, e.g. certificates, its content is the base64 of bytes,
+    /// and its content_type is `base64`. Get it by `get_config_bytes`.
+    /// If it fails, pay attention to err
+    pub fn publish_config_bytes<'p>(
+        &self,
+        py: Python<'p>,
+        data_id: String,
+        group: String,
+        content: &[u8],
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.publish_config_bytes",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
+        let content = encode_bytes(content);
+        let this = self.inner.clone();
+        let connection = self.connection.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                connection
+                    .track(
+                        "publish_config_bytes",
+                        this.publish_config(
+                            data_id,
+                            group,
+                            content,
+                            Some(BINARY_CONTENT_TYPE.to_string()),
+                        ),
+                    )
+                    .await
+                    .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+            }),
+        )
     }
 
     /// Remove config.
@@ -133,15 +279,71 @@ impl AsyncNacosConfigClient {
         data_id: String,
         group: String,
     ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.remove_config",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
         let this = self.inner.clone();
-        future_into_py(py, async move {
-            this.remove_config(data_id, group)
-                .await
-                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
-        })
+        let connection = self.connection.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                connection
+                    .track("remove_config", this.remove_config(data_id, group))
+                    .await
+                    .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+            }),
+        )
+    }
+
+    /// Export the configs of namespace (and group) to a zip, returns the count of them.
+    /// The zip is compatible with the export of Nacos console, with `.metadata.yml` of type, appName and md5.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (namespace, group=None, *, path))]
+    pub fn export_configs<'p>(
+        &self,
+        py: Python<'p>,
+        namespace: String,
+        group: Option<String>,
+        path: String,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span("nacos.export_configs", &[]);
+        let exporter = self.exporter.clone();
+        future_into_py(
+            py,
+            span.instrument(async move { exporter.export(namespace, group, path).await }),
+        )
+    }
+
+    /// Import the configs of the zip, which is exported by `export_configs` or Nacos console,
+    /// into the namespace of this client, with the type and appName of `.metadata.yml`, the one of which
+    /// md5 does not match fails. policy is what to do when the config exists,
+    /// skip(default), overwrite, or abort which imports nothing if any config exists.
+    /// Returns the result of each (data_id, group), one of created, overwritten, skipped, conflict,
+    /// aborted, or the err when it fails.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (path, policy=None))]
+    pub fn import_configs<'p>(
+        &self,
+        py: Python<'p>,
+        path: String,
+        policy: Option<String>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span("nacos.import_configs", &[]);
+        let policy = ImportPolicy::parse(policy)?;
+        let this = self.inner.clone();
+        let connection = self.connection.clone();
+        future_into_py(
+            py,
+            span.instrument(async move { import_configs(&this, &connection, path, policy).await }),
+        )
     }
 
     /// Add NacosConfigChangeListener callback func, which listen the config change.
+    /// When the server is unavailable, it is notified at once of the content in failover dir.
     /// If it fails, pay attention to err
     #[pyo3(signature = (data_id, group, listener))]
     pub fn add_listener<'p>(
@@ -151,6 +353,11 @@ impl AsyncNacosConfigClient {
         group: String,
         listener: Bound<'p, PyAny>, // PyFunction arg: <NacosConfigResponse>
     ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.add_listener",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
         if !listener.is_callable() {
             return Err(PyErr::new::<PyValueError, _>(
                 "Arg `listener` must be a callable",
@@ -158,16 +365,88 @@ impl AsyncNacosConfigClient {
         }
         let listen_wrap = Arc::new(NacosConfigChangeListener {
             func: Arc::new(listener.into()),
+            failover: self.failover.clone(),
+            spans: self.tracer.listener_spans(
+                "nacos.config.notify",
+                &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+                &span,
+            ),
         });
         let this = self.inner.clone();
+        let failover = self.failover.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                this.add_listener(data_id.clone(), group.clone(), listen_wrap.clone())
+                    .await
+                    .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+                crate::metrics::add_subscription("config");
+
+                if let Ok(config_resp) = failover
+                    .get_config("add_listener", &this, data_id, group)
+                    .await
+                    && config_resp.from_failover
+                {
+                    listen_wrap.notify_resp(config_resp);
+                }
+                Ok(())
+            }),
+        )
+    }
+
+    /// Check the server by a round trip of a request over the connection of this client, so its auth
+    /// plugin is checked as well, e.g. for the readiness probe. Returns NacosHealthCheckResult with
+    /// the latency, server address, server version and auth status. The server version is
+    /// best-effort, by the open api under context_path. timeout is in seconds, default 3.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (timeout=None))]
+    pub fn health_check<'p>(
+        &self,
+        py: Python<'p>,
+        timeout: Option<f64>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let timeout = timeout
+            .map(crate::parse_timeout)
+            .transpose()?
+            .unwrap_or(DEFAULT_HEALTH_CHECK_TIMEOUT);
+        let connection = self.connection.clone();
+        let open_api = self.open_api.clone();
         future_into_py(py, async move {
-            this.add_listener(data_id, group, listen_wrap)
-                .await
-                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
-            Ok(())
+            Ok(connection.health_check(&open_api, timeout).await)
         })
     }
 
+    /// State of the connection with server: connecting, connected, disconnected or shutdown.
+    /// It is observed from the results of the requests of this client, and since it is read or called
+    /// back, of a probe request every 5s while no request is answered. Only answered ones count as connected.
+    #[getter]
+    pub fn connection_state(&self) -> &'static str {
+        self.connection.observe();
+        self.connection.state().as_str()
+    }
+
+    /// Address of the server of the gRPC connection while connected, None otherwise. nacos-sdk does
+    /// not expose which server it connects to, so it is only known when server_addr is a single server.
+    #[getter]
+    pub fn server_addr(&self) -> Option<String> {
+        self.connection.server_addr()
+    }
+
+    /// Add the callback of connection state change, which is called with the new state,
+    /// one of connecting, connected, disconnected or shutdown. Its exception is reported by sys.unraisablehook.
+    /// If it fails, pay attention to err
+    pub fn on_connection_change(&self, callback: Bound<'_, PyAny>) -> PyResult<()> {
+        if !callback.is_callable() {
+            return Err(PyErr::new::<PyValueError, _>(
+                "Arg `callback` must be a callable",
+            ));
+        }
+        self.connection.add_callback(callback.unbind());
+        self.connection.observe();
+        Ok(())
+    }
+
     /// Remove NacosConfigChangeListener callback func, but noop....
     /// The logic is not implemented internally, and only APIs are provided as compatibility.
     /// Users maybe do not need it? Not removing the listener is not a big problem, Sorry!
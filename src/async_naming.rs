@@ -1,105 +1,156 @@
 #![deny(clippy::all)]
 
+use crate::future_into_py;
 use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::types::PyAnyMethods;
 use pyo3::{Bound, PyAny, PyErr, PyResult, Python, pyclass, pymethods};
-use pyo3_async_runtimes::tokio::future_into_py;
 
 use std::sync::Arc;
 
+use crate::connection::{ConnectionTracker, DEFAULT_HEALTH_CHECK_TIMEOUT};
+use crate::health_check::{HealthCheck, HealthCheckers};
+use crate::load_balancer::{InstanceSnapshots, LoadBalancer, Strategy};
+use crate::locality::Locality;
 use crate::naming::{
-    NacosNamingEventListener, NacosServiceInstance, transfer_ffi_instance_to_rust,
-    transfer_rust_instance_to_ffi,
+    NacosNamingEventListener, NacosServiceInstance, NacosServiceInstanceList,
+    no_healthy_instance_err, transfer_ffi_instance_to_rust, transfer_rust_instance_to_ffi,
 };
+use crate::open_api::OpenApi;
+use crate::otel::ClientTracer;
+use crate::outlier::OutlierDetector;
+use crate::registration::{RegistrationManager, Registrations};
+use crate::selector::MetadataSelector;
 
 /// Async Client api of Nacos Naming.
 #[pyclass(module = "nacos_sdk_rust_binding_py")]
 pub struct AsyncNacosNamingClient {
     inner: nacos_sdk::api::naming::NamingService,
+    outlier_detector: Arc<OutlierDetector>,
+    locality: Locality,
+    health_checkers: Arc<HealthCheckers>,
+    registrations: Arc<Registrations>,
+    snapshots: Arc<InstanceSnapshots>,
+    connection: Arc<ConnectionTracker>,
+    open_api: Arc<OpenApi>,
+    tracer: ClientTracer,
+    built_in: crate::fork::BuiltIn,
 }
 
 #[pymethods]
 impl AsyncNacosNamingClient {
     /// Build a Naming Client.
     #[new]
-    pub fn new(client_options: crate::ClientOptions) -> PyResult<Self> {
-        let props = nacos_sdk::api::props::ClientProps::new()
-            .server_addr(client_options.server_addr)
-            .namespace(client_options.namespace)
-            .app_name(
-                client_options
-                    .app_name
-                    .unwrap_or(nacos_sdk::api::constants::UNKNOWN.to_string()),
-            )
-            .naming_push_empty_protection(
-                client_options.naming_push_empty_protection.unwrap_or(true),
-            )
-            .naming_load_cache_at_start(client_options.naming_load_cache_at_start.unwrap_or(false));
-
-        let props = if let Some(ep) = client_options.endpoint {
-            props.endpoint(ep)
-        } else {
-            props
-        };
-
-        // need enable_auth_plugin_http with username & password
-        let is_enable_auth_http =
-            client_options.username.is_some() && client_options.password.is_some();
-        // need enable_auth_plugin_aliyun with access_key & access_secret
-        let is_enable_auth_aliyun =
-            client_options.access_key.is_some() && client_options.access_secret.is_some();
-
-        let props = if is_enable_auth_http {
-            props
-                .auth_username(client_options.username.unwrap())
-                .auth_password(client_options.password.unwrap())
-        } else if is_enable_auth_aliyun {
-            props
-                .auth_access_key(client_options.access_key.unwrap())
-                .auth_access_secret(client_options.access_secret.unwrap())
-                .auth_signature_region_id(client_options.signature_region_id.unwrap())
-        } else {
-            props
-        };
+    pub fn new(py: Python<'_>, client_options: crate::ClientOptions) -> PyResult<Self> {
+        let outlier_detector = Arc::new(OutlierDetector::new(&client_options));
+        let locality = Locality::new(&client_options);
+        let connection = Arc::new(ConnectionTracker::new(&client_options));
+        let open_api = Arc::new(OpenApi::new(&client_options)?);
+        let tracer = ClientTracer::new(&client_options);
+        let reconcile_interval_ms = client_options.registration_reconcile_interval_ms;
 
-        let naming_service_builder = if is_enable_auth_http {
-            nacos_sdk::api::naming::NamingServiceBuilder::new(props).enable_auth_plugin_http()
-        } else if is_enable_auth_aliyun {
-            nacos_sdk::api::naming::NamingServiceBuilder::new(props).enable_auth_plugin_aliyun()
-        } else {
-            nacos_sdk::api::naming::NamingServiceBuilder::new(props)
+        let (props, auth_plugin) = client_options.build_client_props()?;
+        let naming_service_builder = match auth_plugin {
+            crate::AuthPlugin::Http => {
+                nacos_sdk::api::naming::NamingServiceBuilder::new(props).enable_auth_plugin_http()
+            }
+            crate::AuthPlugin::Aliyun => {
+                nacos_sdk::api::naming::NamingServiceBuilder::new(props).enable_auth_plugin_aliyun()
+            }
+            crate::AuthPlugin::None => nacos_sdk::api::naming::NamingServiceBuilder::new(props),
         };
 
-        let naming_service = crate::block_on(naming_service_builder.build())
+        let naming_service = py
+            .detach(|| crate::block_on(naming_service_builder.build()))
             .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        connection.set_probe(crate::connection::naming_probe(naming_service.clone()));
+
+        let snapshots = Arc::new(InstanceSnapshots::new(naming_service.clone()));
+        let health_checkers = Arc::new(HealthCheckers::default());
+        let registrations = Registrations::new(
+            naming_service.clone(),
+            health_checkers.clone(),
+            connection.clone(),
+            reconcile_interval_ms,
+        )?;
 
         Ok(Self {
             inner: naming_service,
+            outlier_detector,
+            locality,
+            health_checkers,
+            registrations,
+            snapshots,
+            connection,
+            open_api,
+            tracer,
+            built_in: crate::fork::BuiltIn::current(),
         })
     }
 
-    /// Register instance.
+    /// Register instance, with an optional health_check PyFunction which returns truthy when healthy.
+    /// It is called every health_check_interval_ms (default 5000), and it fails if not returned in
+    /// health_check_timeout_ms (default the interval), e.g. the app is deadlocked. On failure the instance is
+    /// re-registered with healthy=false or deregistered by health_check_failure_action
+    /// (unhealthy(default), deregister), and it is restored when the check passes again.
+    /// The other instances of the service registered by this client are kept.
     /// If it fails, pay attention to err
+    #[pyo3(signature = (service_name, group, service_instance, health_check=None, health_check_interval_ms=None, health_check_timeout_ms=None, health_check_failure_action=None))]
+    #[allow(clippy::too_many_arguments)]
     pub fn register_instance<'p>(
         &self,
         py: Python<'p>,
         service_name: String,
         group: String,
         service_instance: NacosServiceInstance,
+        health_check: Option<Bound<'p, PyAny>>,
+        health_check_interval_ms: Option<u64>,
+        health_check_timeout_ms: Option<u64>,
+        health_check_failure_action: Option<String>,
     ) -> PyResult<Bound<'p, PyAny>> {
-        let this = self.inner.clone();
-        future_into_py(py, async move {
-            this.register_instance(
-                service_name,
-                Some(group),
-                transfer_ffi_instance_to_rust(&service_instance),
-            )
-            .await
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
-        })
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.register_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        let health_check = HealthCheck::parse(
+            health_check,
+            health_check_interval_ms,
+            health_check_timeout_ms,
+            health_check_failure_action,
+        )?;
+        let rust_instance = transfer_ffi_instance_to_rust(&service_instance);
+        self.health_checkers
+            .stop(&service_name, &group, &rust_instance.ip, rust_instance.port);
+
+        let health_checkers = self.health_checkers.clone();
+        let registrations = self.registrations.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                // along with the other instances of the service registered by this client
+                registrations
+                    .register(&service_name, &group, rust_instance.clone())
+                    .await?;
+
+                if let Some(health_check) = health_check {
+                    health_checkers.start(
+                        Arc::downgrade(&registrations),
+                        service_name,
+                        group,
+                        &rust_instance.ip,
+                        rust_instance.port,
+                        health_check,
+                    );
+                }
+                Ok(())
+            }),
+        )
     }
 
-    /// Deregister instance.
+    /// Deregister instance, the other instances of the service registered by this client are kept.
     /// If it fails, pay attention to err
     pub fn deregister_instance<'p>(
         &self,
@@ -108,19 +159,35 @@ impl AsyncNacosNamingClient {
         group: String,
         service_instance: NacosServiceInstance,
     ) -> PyResult<Bound<'p, PyAny>> {
-        let this = self.inner.clone();
-        future_into_py(py, async move {
-            this.deregister_instance(
-                service_name,
-                Some(group),
-                transfer_ffi_instance_to_rust(&service_instance),
-            )
-            .await
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
-        })
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.deregister_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        let registrations = self.registrations.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                // the other instances of the service registered by this client are kept
+                let rust_instance = transfer_ffi_instance_to_rust(&service_instance);
+                registrations
+                    .deregister(
+                        "deregister_instance",
+                        &service_name,
+                        &group,
+                        std::slice::from_ref(&rust_instance),
+                    )
+                    .await
+                    .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+            }),
+        )
     }
 
     /// Batch register instance, improve interaction efficiency.
+    /// It replaces the instances of the service registered by this client.
     /// If it fails, pay attention to err
     pub fn batch_register_instance<'p>(
         &self,
@@ -129,21 +196,131 @@ impl AsyncNacosNamingClient {
         group: String,
         service_instances: Vec<NacosServiceInstance>,
     ) -> PyResult<Bound<'p, PyAny>> {
-        let this = self.inner.clone();
-        future_into_py(py, async move {
-            let rust_instances = service_instances
-                .iter()
-                .map(transfer_ffi_instance_to_rust)
-                .collect();
-            this.batch_register_instance(service_name, Some(group), rust_instances)
-                .await
-                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
-        })
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.batch_register_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        let registrations = self.registrations.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                let rust_instances: Vec<nacos_sdk::api::naming::ServiceInstance> =
+                    service_instances
+                        .iter()
+                        .map(transfer_ffi_instance_to_rust)
+                        .collect();
+                // it replaces all the instances of the service registered by this client
+                registrations
+                    .batch_register(&service_name, &group, rust_instances)
+                    .await
+            }),
+        )
+    }
+
+    /// Batch deregister instances of the service, the remaining instances of the service registered by
+    /// this client are published together. Returns the result of each instance, None on success, otherwise the err,
+    /// which is ValueError for the instance not registered by this client.
+    pub fn batch_deregister_instance<'p>(
+        &self,
+        py: Python<'p>,
+        service_name: String,
+        group: String,
+        service_instances: Vec<NacosServiceInstance>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.batch_deregister_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        let registrations = self.registrations.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                let rust_instances = service_instances
+                    .iter()
+                    .map(transfer_ffi_instance_to_rust)
+                    .collect();
+                Ok(registrations
+                    .deregister_many(service_name, group, rust_instances)
+                    .await)
+            }),
+        )
+    }
+
+    /// Register instances of several services, each item is (service_name, group, service_instance),
+    /// the ones of a service are published together, it continues on error of a service.
+    /// Returns the result of each item, None on success, otherwise the err of its service.
+    pub fn register_many<'p>(
+        &self,
+        py: Python<'p>,
+        registrations: Vec<(String, String, NacosServiceInstance)>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span("nacos.register_many", &[]);
+        let this = self.registrations.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                let rust_registrations = registrations
+                    .iter()
+                    .map(|(service_name, group, service_instance)| {
+                        (
+                            service_name.clone(),
+                            group.clone(),
+                            transfer_ffi_instance_to_rust(service_instance),
+                        )
+                    })
+                    .collect();
+                Ok(this.register_many(rust_registrations).await)
+            }),
+        )
+    }
+
+    /// Update the registered instance in place, e.g. weight, metadata, or enabled=False / weight=0
+    /// to drain traffic. It re-registers without deregister, so the consumers see no flap.
+    /// It raises ValueError when the instance is not registered by this client or does not exist in server.
+    /// If it fails, pay attention to err
+    pub fn update_instance<'p>(
+        &self,
+        py: Python<'p>,
+        service_name: String,
+        group: String,
+        service_instance: NacosServiceInstance,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.update_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        let registrations = self.registrations.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                registrations
+                    .update_instance(
+                        service_name,
+                        group,
+                        transfer_ffi_instance_to_rust(&service_instance),
+                    )
+                    .await
+            }),
+        )
     }
 
     /// Get all instances by service and group. default cluster=[], subscribe=true.
+    /// metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
     /// If it fails, pay attention to err
-    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None))]
+    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None, metadata_selector=None))]
     pub fn get_all_instances<'p>(
         &self,
         py: Python<'p>,
@@ -151,29 +328,48 @@ impl AsyncNacosNamingClient {
         group: String,
         clusters: Option<Vec<String>>,
         subscribe: Option<bool>,
+        metadata_selector: Option<Bound<'p, PyAny>>,
     ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.get_all_instances",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        let metadata_selector = MetadataSelector::parse(metadata_selector)?;
         let this = self.inner.clone();
-        future_into_py(py, async move {
-            let rust_instances = this
-                .get_all_instances(
-                    service_name,
-                    Some(group),
-                    clusters.unwrap_or_default(),
-                    subscribe.unwrap_or(true),
-                )
-                .await
-                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
-
-            Ok(rust_instances
-                .iter()
-                .map(transfer_rust_instance_to_ffi)
-                .collect::<Vec<NacosServiceInstance>>())
-        })
+        let connection = self.connection.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                let rust_instances = connection
+                    .track(
+                        "get_all_instances",
+                        this.get_all_instances(
+                            service_name,
+                            Some(group),
+                            clusters.unwrap_or_default(),
+                            subscribe.unwrap_or(true),
+                        ),
+                    )
+                    .await
+                    .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+                let rust_instances = metadata_selector.filter(rust_instances);
+
+                Ok(NacosServiceInstanceList::from_rust(Arc::new(
+                    rust_instances,
+                )))
+            }),
+        )
     }
 
     /// Select instances whether healthy or not. default cluster=[], subscribe=true, healthy=true.
+    /// metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
     /// If it fails, pay attention to err
-    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None, healthy=None))]
+    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None, healthy=None, metadata_selector=None))]
+    #[allow(clippy::too_many_arguments)]
     pub fn select_instances<'p>(
         &self,
         py: Python<'p>,
@@ -182,29 +378,59 @@ impl AsyncNacosNamingClient {
         clusters: Option<Vec<String>>,
         subscribe: Option<bool>,
         healthy: Option<bool>,
+        metadata_selector: Option<Bound<'p, PyAny>>,
     ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.select_instances",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        let metadata_selector = MetadataSelector::parse(metadata_selector)?;
+        let healthy = healthy.unwrap_or(true);
         let this = self.inner.clone();
-        future_into_py(py, async move {
-            let rust_instances = this
-                .select_instances(
-                    service_name,
-                    Some(group),
-                    clusters.unwrap_or_default(),
-                    subscribe.unwrap_or(true),
-                    healthy.unwrap_or(true),
-                )
-                .await
-                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
-            Ok(rust_instances
-                .iter()
-                .map(transfer_rust_instance_to_ffi)
-                .collect::<Vec<NacosServiceInstance>>())
-        })
+        let outlier_detector = self.outlier_detector.clone();
+        let connection = self.connection.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                let rust_instances = connection
+                    .track(
+                        "select_instances",
+                        this.select_instances(
+                            service_name,
+                            Some(group),
+                            clusters.unwrap_or_default(),
+                            subscribe.unwrap_or(true),
+                            healthy,
+                        ),
+                    )
+                    .await
+                    .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+                let rust_instances = metadata_selector.filter(rust_instances);
+                // the ejected instances are not healthy
+                let rust_instances = if healthy {
+                    outlier_detector.filter(rust_instances)
+                } else {
+                    rust_instances
+                };
+                Ok(NacosServiceInstanceList::from_rust(Arc::new(
+                    rust_instances,
+                )))
+            }),
+        )
     }
 
     /// Select one healthy instance. default cluster=[], subscribe=true.
+    /// metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
+    /// It prefers the instances in the same zone or cluster, by locality_zone and locality_cluster
+    /// of the args or ClientOptions, and falls back to all the zones when the healthy local
+    /// instances are below locality_min_healthy_percent.
     /// If it fails, pay attention to err
-    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None))]
+    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None, metadata_selector=None, locality_zone=None, locality_cluster=None))]
+    #[allow(clippy::too_many_arguments)]
     pub fn select_one_healthy_instance<'p>(
         &self,
         py: Python<'p>,
@@ -212,20 +438,47 @@ impl AsyncNacosNamingClient {
         group: String,
         clusters: Option<Vec<String>>,
         subscribe: Option<bool>,
+        metadata_selector: Option<Bound<'p, PyAny>>,
+        locality_zone: Option<String>,
+        locality_cluster: Option<String>,
     ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.select_one_healthy_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        let metadata_selector = MetadataSelector::parse(metadata_selector)?;
+        let locality = self.locality.with(locality_zone, locality_cluster);
         let this = self.inner.clone();
-        future_into_py(py, async move {
-            let rust_instance = this
-                .select_one_healthy_instance(
-                    service_name,
-                    Some(group),
-                    clusters.unwrap_or_default(),
-                    subscribe.unwrap_or(true),
-                )
-                .await
-                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
-            Ok(transfer_rust_instance_to_ffi(&rust_instance))
-        })
+        let outlier_detector = self.outlier_detector.clone();
+        let connection = self.connection.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                // all instances, the unhealthy local ones count for the locality
+                let rust_instances = connection
+                    .track(
+                        "select_one_healthy_instance",
+                        this.get_all_instances(
+                            service_name.clone(),
+                            Some(group.clone()),
+                            clusters.unwrap_or_default(),
+                            subscribe.unwrap_or(true),
+                        ),
+                    )
+                    .await
+                    .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+                let rust_instances = locality
+                    .prefer_local(metadata_selector.filter(rust_instances), &outlier_detector);
+                let rust_instance = outlier_detector
+                    .select_one(rust_instances)
+                    .ok_or_else(|| no_healthy_instance_err(&service_name, &group))?;
+                Ok(transfer_rust_instance_to_ffi(&rust_instance))
+            }),
+        )
     }
 
     /// Add NacosNamingEventListener callback func, which listen the instance change.
@@ -239,6 +492,14 @@ impl AsyncNacosNamingClient {
         clusters: Option<Vec<String>>,
         listener: Bound<'p, PyAny>, // PyFunction arg: Vec<NacosServiceInstance>
     ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.subscribe",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
         if !listener.is_callable() {
             return Err(PyErr::new::<PyValueError, _>(
                 "Arg `listener` must be a callable",
@@ -246,22 +507,168 @@ impl AsyncNacosNamingClient {
         }
         let listen_wrap = Arc::new(NacosNamingEventListener {
             func: Arc::new(listener.into()),
+            spans: self.tracer.listener_spans(
+                "nacos.naming.notify",
+                &[
+                    ("nacos.service_name", &service_name),
+                    ("nacos.group", &group),
+                ],
+                &span,
+            ),
         });
         let this = self.inner.clone();
 
+        let connection = self.connection.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                connection
+                    .track(
+                        "subscribe",
+                        this.subscribe(
+                            service_name,
+                            Some(group),
+                            clusters.unwrap_or_default(),
+                            listen_wrap,
+                        ),
+                    )
+                    .await
+                    .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+                crate::metrics::add_subscription("naming");
+                Ok(())
+            }),
+        )
+    }
+
+    /// Build a LoadBalancer of service, which keeps a local instance snapshot updated through subscribe.
+    /// The snapshot is shared by the LoadBalancers of the same service, group and clusters, until all of them are closed.
+    /// strategy: weighted_random(default), round_robin, least_recently_used, consistent_hash.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (service_name, group, clusters=None, strategy=None))]
+    pub fn load_balancer<'p>(
+        &self,
+        py: Python<'p>,
+        service_name: String,
+        group: String,
+        clusters: Option<Vec<String>>,
+        strategy: Option<String>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.load_balancer",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        let strategy = Strategy::parse(strategy)?;
+        let clusters = clusters.unwrap_or_default();
+        let snapshots = self.snapshots.clone();
+        let outlier_detector = self.outlier_detector.clone();
+        let connection = self.connection.clone();
+        future_into_py(
+            py,
+            span.instrument(async move {
+                let snapshot = connection
+                    .track(
+                        "load_balancer",
+                        snapshots.acquire(service_name.clone(), group.clone(), clusters.clone()),
+                    )
+                    .await
+                    .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+                Ok(LoadBalancer::new(
+                    service_name,
+                    group,
+                    clusters,
+                    strategy,
+                    snapshot,
+                    snapshots,
+                    outlier_detector,
+                ))
+            }),
+        )
+    }
+
+    /// Report a successful call to the instance, which resets its consecutive failures.
+    pub fn report_success(&self, service_instance: NacosServiceInstance) {
+        self.outlier_detector
+            .report_success(&service_instance.ip, service_instance.port);
+    }
+
+    /// Report a failed call to the instance, it will be ejected from the select results
+    /// temporarily when the consecutive failures exceed the threshold.
+    pub fn report_failure(&self, service_instance: NacosServiceInstance) {
+        self.outlier_detector
+            .report_failure(&service_instance.ip, service_instance.port);
+    }
+
+    /// Registration manager of the instances registered through this client.
+    #[getter]
+    pub fn registration_manager(&self) -> RegistrationManager {
+        RegistrationManager::new(self.registrations.clone(), self.built_in, true)
+    }
+
+    /// Close the client, deregister all the instances registered through it.
+    /// If it fails, pay attention to err
+    pub fn close<'p>(&self, py: Python<'p>) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let registrations = self.registrations.clone();
+        future_into_py(py, async move { registrations.close().await })
+    }
+
+    /// Check the server by a round trip of a request over the connection of this client, so its auth
+    /// plugin is checked as well, e.g. for the readiness probe. Returns NacosHealthCheckResult with
+    /// the latency, server address, server version and auth status. The server version is
+    /// best-effort, by the open api under context_path. timeout is in seconds, default 3.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (timeout=None))]
+    pub fn health_check<'p>(
+        &self,
+        py: Python<'p>,
+        timeout: Option<f64>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
+        let timeout = timeout
+            .map(crate::parse_timeout)
+            .transpose()?
+            .unwrap_or(DEFAULT_HEALTH_CHECK_TIMEOUT);
+        let connection = self.connection.clone();
+        let open_api = self.open_api.clone();
         future_into_py(py, async move {
-            this.subscribe(
-                service_name,
-                Some(group),
-                clusters.unwrap_or_default(),
-                listen_wrap,
-            )
-            .await
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
-            Ok(())
+            Ok(connection.health_check(&open_api, timeout).await)
         })
     }
 
+    /// State of the connection with server: connecting, connected, disconnected or shutdown.
+    /// It is observed from the results of the requests of this client, and since it is read or called
+    /// back, of a probe request every 5s while no request is answered. Only answered ones count as connected.
+    #[getter]
+    pub fn connection_state(&self) -> &'static str {
+        self.connection.observe();
+        self.connection.state().as_str()
+    }
+
+    /// Address of the server of the gRPC connection while connected, None otherwise. nacos-sdk does
+    /// not expose which server it connects to, so it is only known when server_addr is a single server.
+    #[getter]
+    pub fn server_addr(&self) -> Option<String> {
+        self.connection.server_addr()
+    }
+
+    /// Add the callback of connection state change, which is called with the new state,
+    /// one of connecting, connected, disconnected or shutdown. Its exception is reported by sys.unraisablehook.
+    /// If it fails, pay attention to err
+    pub fn on_connection_change(&self, callback: Bound<'_, PyAny>) -> PyResult<()> {
+        if !callback.is_callable() {
+            return Err(PyErr::new::<PyValueError, _>(
+                "Arg `callback` must be a callable",
+            ));
+        }
+        self.connection.add_callback(callback.unbind());
+        self.connection.observe();
+        Ok(())
+    }
+
     /// Remove NacosNamingEventListener callback func, but noop....
     /// The logic is not implemented internally, and only APIs are provided as compatibility.
     /// Users maybe do not need it? Not removing the subscription is not a big problem, Sorry!
@@ -275,6 +682,7 @@ impl AsyncNacosNamingClient {
         clusters: Option<Vec<String>>,
         listener: Bound<'p, PyAny>, // PyFunction arg: Vec<NacosServiceInstance>
     ) -> PyResult<Bound<'p, PyAny>> {
+        self.built_in.check()?;
         future_into_py(py, async move { Ok(()) })
     }
 }
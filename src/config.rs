@@ -1,134 +1,416 @@
 #![deny(clippy::all)]
 
-use pyo3::exceptions::{PyRuntimeError, PyValueError};
-use pyo3::types::PyAnyMethods;
-use pyo3::{Bound, Py, PyAny, PyErr, PyResult, Python, pyclass, pymethods};
+use pyo3::exceptions::{PyRuntimeError, PyTimeoutError, PyValueError};
+use pyo3::types::{PyAnyMethods, PyBytes, PyDict, PyDictMethods};
+use pyo3::{Bound, Py, PyAny, PyErr, PyRef, PyResult, Python, pyclass, pymethods};
 
-use std::sync::Arc;
+use base64::Engine;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config_transfer::{ConfigExporter, ImportPolicy, ImportStatus, import_configs};
+use crate::connection::{ConnectionTracker, DEFAULT_HEALTH_CHECK_TIMEOUT, NacosHealthCheckResult};
+use crate::failover::ConfigFailover;
+use crate::open_api::OpenApi;
+use crate::otel::{ClientTracer, ListenerSpans};
+
+/// Default max count of the configs got concurrently by `get_configs`.
+const DEFAULT_GET_CONFIGS_CONCURRENCY: usize = 16;
+/// Content type of the config published by `publish_config_bytes`, its content is the base64 of bytes.
+pub(crate) const BINARY_CONTENT_TYPE: &str = "base64";
 
 /// Client api of Nacos Config.
 #[pyclass(module = "nacos_sdk_rust_binding_py")]
 pub struct NacosConfigClient {
     inner: nacos_sdk::api::config::ConfigService,
+    exporter: Arc<ConfigExporter>,
+    failover: Arc<ConfigFailover>,
+    connection: Arc<ConnectionTracker>,
+    open_api: Arc<OpenApi>,
+    tracer: ClientTracer,
+    built_in: crate::fork::BuiltIn,
 }
 
 #[pymethods]
 impl NacosConfigClient {
     /// Build a Config Client.
     #[new]
-    pub fn new(client_options: crate::ClientOptions) -> PyResult<Self> {
-        let props = nacos_sdk::api::props::ClientProps::new()
-            .server_addr(client_options.server_addr)
-            .namespace(client_options.namespace)
-            .app_name(
-                client_options
-                    .app_name
-                    .unwrap_or(nacos_sdk::api::constants::UNKNOWN.to_string()),
-            )
-            .config_load_cache_at_start(client_options.config_load_cache_at_start.unwrap_or(false));
-
-        let props = if let Some(ep) = client_options.endpoint {
-            props.endpoint(ep)
-        } else {
-            props
-        };
+    pub fn new(py: Python<'_>, client_options: crate::ClientOptions) -> PyResult<Self> {
+        let open_api = Arc::new(OpenApi::new(&client_options)?);
+        let tracer = ClientTracer::new(&client_options);
+        let exporter = Arc::new(ConfigExporter::new(open_api.clone()));
+        let connection = Arc::new(ConnectionTracker::new(&client_options));
+        let failover = Arc::new(ConfigFailover::new(&client_options, connection.clone()));
 
-        // need enable_auth_plugin_http with username & password
-        let is_enable_auth_http =
-            client_options.username.is_some() && client_options.password.is_some();
-        // need enable_auth_plugin_aliyun with access_key & access_secret
-        let is_enable_auth_aliyun =
-            client_options.access_key.is_some() && client_options.access_secret.is_some();
-
-        let props = if is_enable_auth_http {
-            props
-                .auth_username(client_options.username.unwrap())
-                .auth_password(client_options.password.unwrap())
-        } else if is_enable_auth_aliyun {
-            props
-                .auth_access_key(client_options.access_key.unwrap())
-                .auth_access_secret(client_options.access_secret.unwrap())
-                .auth_signature_region_id(client_options.signature_region_id.unwrap())
-        } else {
-            props
+        let (props, auth_plugin) = client_options.build_client_props()?;
+        let config_service_builder = match auth_plugin {
+            crate::AuthPlugin::Http => {
+                nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_http()
+            }
+            crate::AuthPlugin::Aliyun => {
+                nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_aliyun()
+            }
+            crate::AuthPlugin::None => nacos_sdk::api::config::ConfigServiceBuilder::new(props),
         };
 
-        let config_service_builder = if is_enable_auth_http {
-            nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_http()
-        } else if is_enable_auth_aliyun {
-            nacos_sdk::api::config::ConfigServiceBuilder::new(props).enable_auth_plugin_aliyun()
-        } else {
-            nacos_sdk::api::config::ConfigServiceBuilder::new(props)
-        };
-
-        let config_service = crate::block_on(config_service_builder.build())
+        let config_service = py
+            .detach(|| crate::block_on(config_service_builder.build()))
             .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        connection.set_probe(crate::connection::config_probe(config_service.clone()));
 
         Ok(NacosConfigClient {
             inner: config_service,
+            exporter,
+            failover,
+            connection,
+            open_api,
+            tracer,
+            built_in: crate::fork::BuiltIn::current(),
+        })
+    }
+
+    /// Get config's content, or the default when the config is not found.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (data_id, group, default=None))]
+    pub fn get_config(
+        &self,
+        py: Python<'_>,
+        data_id: String,
+        group: String,
+        default: Option<String>,
+    ) -> PyResult<String> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.get_config",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
+        span.in_scope(|| {
+            py.detach(|| {
+                crate::block_on(
+                    self.failover
+                        .get_config_or(&self.inner, data_id, group, default),
+                )
+            })
         })
     }
 
-    /// Get config's content.
+    /// Get config's content as bytes, decoded from base64 if its content_type is `base64`
+    /// as published by `publish_config_bytes`, otherwise encoded by utf-8.
     /// If it fails, pay attention to err
-    pub fn get_config(&self, data_id: String, group: String) -> PyResult<String> {
-        let resp = self.get_config_resp(data_id, group)?;
-        Ok(resp.content)
+    pub fn get_config_bytes<'p>(
+        &self,
+        py: Python<'p>,
+        data_id: String,
+        group: String,
+    ) -> PyResult<Bound<'p, PyBytes>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.get_config_bytes",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
+        span.in_scope(|| {
+            let config_resp = py.detach(|| {
+                crate::block_on(self.failover.get_config(
+                    "get_config_bytes",
+                    &self.inner,
+                    data_id,
+                    group,
+                ))
+            })?;
+            Ok(PyBytes::new(py, &config_resp.bytes()?))
+        })
     }
 
     /// Get NacosConfigResponse.
+    /// It is served from the failover dir of ClientOptions when the server is unavailable.
+    /// with_metadata: also get last_modified, encrypted_data_key, beta and tags by the open api
+    /// of server, which the SDK does not expose, default false.
     /// If it fails, pay attention to err
-    pub fn get_config_resp(&self, data_id: String, group: String) -> PyResult<NacosConfigResponse> {
-        let future = self.inner.get_config(data_id, group);
-        let config_resp = crate::block_on(future)
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
-        Ok(transfer_conf_resp(config_resp))
+    #[pyo3(signature = (data_id, group, with_metadata=false))]
+    pub fn get_config_resp(
+        &self,
+        py: Python<'_>,
+        data_id: String,
+        group: String,
+        with_metadata: bool,
+    ) -> PyResult<NacosConfigResponse> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.get_config_resp",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
+        span.in_scope(|| {
+            let mut config_resp = py.detach(|| {
+                crate::block_on(self.failover.get_config(
+                    "get_config_resp",
+                    &self.inner,
+                    data_id,
+                    group,
+                ))
+            })?;
+            if with_metadata && !config_resp.from_failover {
+                py.detach(|| crate::block_on(fill_metadata(&self.open_api, &mut config_resp)))?;
+            }
+            Ok(config_resp)
+        })
+    }
+
+    /// Get the configs of [(data_id, group), ...] concurrently, at most max_concurrency at a time,
+    /// default 16. Returns a dict of (data_id, group) to NacosConfigResponse, or the err when it fails.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (keys, max_concurrency=None))]
+    pub fn get_configs<'p>(
+        &self,
+        py: Python<'p>,
+        keys: Vec<(String, String)>,
+        max_concurrency: Option<usize>,
+    ) -> PyResult<Bound<'p, PyDict>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span("nacos.get_configs", &[]);
+        span.in_scope(|| {
+            let results = py.detach(|| {
+                crate::block_on(get_configs(
+                    self.inner.clone(),
+                    self.failover.clone(),
+                    keys,
+                    max_concurrency,
+                ))
+            })?;
+            into_py_dict(py, results)
+        })
+    }
+
+    /// Wait until the config appears, returns its NacosConfigResponse.
+    /// Raise TimeoutError if it does not appear in timeout seconds.
+    /// If it fails, pay attention to err
+    pub fn wait_for_config(
+        &self,
+        py: Python<'_>,
+        data_id: String,
+        group: String,
+        timeout: f64,
+    ) -> PyResult<NacosConfigResponse> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.wait_for_config",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
+        span.in_scope(|| {
+            let timeout = crate::parse_timeout(timeout)?;
+            let this = self.inner.clone();
+            let failover = self.failover.clone();
+            // release the GIL while waiting, so the other python threads keep running
+            py.detach(|| crate::block_on(wait_for_config(this, failover, data_id, group, timeout)))
+        })
     }
 
     /// Publish config.
     /// If it fails, pay attention to err
     pub fn publish_config(
         &self,
+        py: Python<'_>,
         data_id: String,
         group: String,
         content: String,
     ) -> PyResult<bool> {
-        let future = self.inner.publish_config(data_id, group, content, None);
-        crate::block_on(future)
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.publish_config",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
+        span.in_scope(|| {
+            let future = self.inner.publish_config(data_id, group, content, None);
+            py.detach(|| crate::block_on(self.connection.track("publish_config", future)))
+                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+        })
+    }
+
+    /// Publish bytes as config, e.g. certificates, its content is the base64 of bytes,
+    /// and its content_type is `base64`. Get it by `get_config_bytes`.
+    /// If it fails, pay attention to err
+    pub fn publish_config_bytes(
+        &self,
+        py: Python<'_>,
+        data_id: String,
+        group: String,
+        content: &[u8],
+    ) -> PyResult<bool> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.publish_config_bytes",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
+        span.in_scope(|| {
+            let future = self.inner.publish_config(
+                data_id,
+                group,
+                encode_bytes(content),
+                Some(BINARY_CONTENT_TYPE.to_string()),
+            );
+            py.detach(|| crate::block_on(self.connection.track("publish_config_bytes", future)))
+                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+        })
     }
 
     /// Remove config.
     /// If it fails, pay attention to err
-    pub fn remove_config(&self, data_id: String, group: String) -> PyResult<bool> {
-        let future = self.inner.remove_config(data_id, group);
-        crate::block_on(future)
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+    pub fn remove_config(&self, py: Python<'_>, data_id: String, group: String) -> PyResult<bool> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.remove_config",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
+        span.in_scope(|| {
+            let future = self.inner.remove_config(data_id, group);
+            py.detach(|| crate::block_on(self.connection.track("remove_config", future)))
+                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+        })
+    }
+
+    /// Export the configs of namespace (and group) to a zip, returns the count of them.
+    /// The zip is compatible with the export of Nacos console, with `.metadata.yml` of type, appName and md5.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (namespace, group=None, *, path))]
+    pub fn export_configs(
+        &self,
+        py: Python<'_>,
+        namespace: String,
+        group: Option<String>,
+        path: String,
+    ) -> PyResult<usize> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span("nacos.export_configs", &[]);
+        span.in_scope(|| {
+            py.detach(|| crate::block_on(self.exporter.export(namespace, group, path)))
+        })
+    }
+
+    /// Import the configs of the zip, which is exported by `export_configs` or Nacos console,
+    /// into the namespace of this client, with the type and appName of `.metadata.yml`, the one of which
+    /// md5 does not match fails. policy is what to do when the config exists,
+    /// skip(default), overwrite, or abort which imports nothing if any config exists.
+    /// Returns the result of each (data_id, group), one of created, overwritten, skipped, conflict,
+    /// aborted, or the err when it fails.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (path, policy=None))]
+    pub fn import_configs(
+        &self,
+        py: Python<'_>,
+        path: String,
+        policy: Option<String>,
+    ) -> PyResult<HashMap<(String, String), ImportStatus>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span("nacos.import_configs", &[]);
+        span.in_scope(|| {
+            let policy = ImportPolicy::parse(policy)?;
+            py.detach(|| {
+                crate::block_on(import_configs(&self.inner, &self.connection, path, policy))
+            })
+        })
     }
 
     /// Add NacosConfigChangeListener callback func, which listen the config change.
+    /// When the server is unavailable, it is notified at once of the content in failover dir.
     /// If it fails, pay attention to err
     #[pyo3(signature = (data_id, group, listener))]
     pub fn add_listener<'p>(
         &self,
+        py: Python<'_>,
         data_id: String,
         group: String,
         listener: Bound<'p, PyAny>, // PyFunction arg: <NacosConfigResponse>
     ) -> PyResult<()> {
-        if !listener.is_callable() {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.add_listener",
+            &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+        );
+        span.in_scope(|| {
+            if !listener.is_callable() {
+                return Err(PyErr::new::<PyValueError, _>(
+                    "Arg `listener` must be a callable",
+                ));
+            }
+            let listen_wrap = Arc::new(NacosConfigChangeListener {
+                func: Arc::new(listener.into()),
+                failover: self.failover.clone(),
+                spans: self.tracer.listener_spans(
+                    "nacos.config.notify",
+                    &[("nacos.data_id", &data_id), ("nacos.group", &group)],
+                    &span,
+                ),
+            });
+            let future =
+                self.inner
+                    .add_listener(data_id.clone(), group.clone(), listen_wrap.clone());
+            py.detach(|| crate::block_on(future))
+                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+            crate::metrics::add_subscription("config");
+
+            if let Ok(config_resp) = py.detach(|| {
+                crate::block_on(self.failover.get_config(
+                    "add_listener",
+                    &self.inner,
+                    data_id,
+                    group,
+                ))
+            }) && config_resp.from_failover
+            {
+                listen_wrap.notify_resp(config_resp);
+            }
+            Ok(())
+        })
+    }
+
+    /// Check the server by a round trip of a request over the connection of this client, so its auth
+    /// plugin is checked as well, e.g. for the readiness probe. Returns NacosHealthCheckResult with
+    /// the latency, server address, server version and auth status. The server version is
+    /// best-effort, by the open api under context_path. timeout is in seconds, default 3.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (timeout=None))]
+    pub fn health_check(
+        &self,
+        py: Python<'_>,
+        timeout: Option<f64>,
+    ) -> PyResult<NacosHealthCheckResult> {
+        self.built_in.check()?;
+        let timeout = timeout
+            .map(crate::parse_timeout)
+            .transpose()?
+            .unwrap_or(DEFAULT_HEALTH_CHECK_TIMEOUT);
+        // release the GIL while waiting, so the other python threads keep running
+        Ok(py.detach(|| crate::block_on(self.connection.health_check(&self.open_api, timeout))))
+    }
+
+    /// State of the connection with server: connecting, connected, disconnected or shutdown.
+    /// It is observed from the results of the requests of this client, and since it is read or called
+    /// back, of a probe request every 5s while no request is answered. Only answered ones count as connected.
+    #[getter]
+    pub fn connection_state(&self) -> &'static str {
+        self.connection.observe();
+        self.connection.state().as_str()
+    }
+
+    /// Address of the server of the gRPC connection while connected, None otherwise. nacos-sdk does
+    /// not expose which server it connects to, so it is only known when server_addr is a single server.
+    #[getter]
+    pub fn server_addr(&self) -> Option<String> {
+        self.connection.server_addr()
+    }
+
+    /// Add the callback of connection state change, which is called with the new state,
+    /// one of connecting, connected, disconnected or shutdown. Its exception is reported by sys.unraisablehook.
+    /// If it fails, pay attention to err
+    pub fn on_connection_change(&self, callback: Bound<'_, PyAny>) -> PyResult<()> {
+        if !callback.is_callable() {
             return Err(PyErr::new::<PyValueError, _>(
-                "Arg `listener` must be a callable",
+                "Arg `callback` must be a callable",
             ));
         }
-        let future = self.inner.add_listener(
-            data_id,
-            group,
-            Arc::new(NacosConfigChangeListener {
-                func: Arc::new(listener.into()),
-            }),
-        );
-        crate::block_on(future)
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        self.connection.add_callback(callback.unbind());
+        self.connection.observe();
         Ok(())
     }
 
@@ -147,7 +429,8 @@ impl NacosConfigClient {
     }
 }
 
-#[pyclass(module = "nacos_sdk_rust_binding_py")]
+#[pyclass(module = "nacos_sdk_rust_binding_py", from_py_object)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct NacosConfigResponse {
     /// Namespace/Tenant
     #[pyo3(get)]
@@ -167,22 +450,332 @@ pub struct NacosConfigResponse {
     /// Content's md5
     #[pyo3(get)]
     pub md5: String,
+    /// Whether it is served from the failover dir, because the server is unavailable
+    #[pyo3(get)]
+    pub from_failover: bool,
+    /// Last modified time in milliseconds since epoch, None unless got with metadata
+    #[pyo3(get)]
+    pub last_modified: Option<i64>,
+    /// Data key of the encrypted content, None if not encrypted or not got with metadata
+    #[pyo3(get)]
+    pub encrypted_data_key: Option<String>,
+    /// Whether a beta release of it is in progress, false unless got with metadata
+    #[pyo3(get)]
+    pub beta: bool,
+    /// Tags of config, empty unless got with metadata
+    #[pyo3(get)]
+    pub tags: Vec<String>,
+}
+
+#[pymethods]
+impl NacosConfigResponse {
+    /// Convert to dict, the keys are the field names.
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("namespace", self.namespace.clone())?;
+        dict.set_item("data_id", self.data_id.clone())?;
+        dict.set_item("group", self.group.clone())?;
+        dict.set_item("content", self.content.clone())?;
+        dict.set_item("content_type", self.content_type.clone())?;
+        dict.set_item("md5", self.md5.clone())?;
+        dict.set_item("from_failover", self.from_failover)?;
+        dict.set_item("last_modified", self.last_modified)?;
+        dict.set_item("encrypted_data_key", self.encrypted_data_key.clone())?;
+        dict.set_item("beta", self.beta)?;
+        dict.set_item("tags", self.tags.clone())?;
+        Ok(dict)
+    }
+
+    /// Content as bytes, decoded from base64 if its content_type is `base64`
+    /// as published by `publish_config_bytes`, otherwise encoded by utf-8.
+    /// If it fails, pay attention to err
+    #[getter]
+    pub fn content_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new(py, &self.bytes()?))
+    }
+
+    /// Build from dict, which is the same as the result of `to_dict()`. All keys are required,
+    /// except `from_failover` and the metadata: `last_modified`, `encrypted_data_key`, `beta`, `tags`.
+    #[staticmethod]
+    pub fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<NacosConfigResponse> {
+        Ok(Self {
+            namespace: crate::extract_dict_item_required(dict, "namespace")?,
+            data_id: crate::extract_dict_item_required(dict, "data_id")?,
+            group: crate::extract_dict_item_required(dict, "group")?,
+            content: crate::extract_dict_item_required(dict, "content")?,
+            content_type: crate::extract_dict_item_required(dict, "content_type")?,
+            md5: crate::extract_dict_item_required(dict, "md5")?,
+            from_failover: crate::extract_dict_item(dict, "from_failover")?.unwrap_or(false),
+            last_modified: crate::extract_dict_item(dict, "last_modified")?,
+            encrypted_data_key: crate::extract_dict_item(dict, "encrypted_data_key")?,
+            beta: crate::extract_dict_item(dict, "beta")?.unwrap_or(false),
+            tags: crate::extract_dict_item(dict, "tags")?.unwrap_or_default(),
+        })
+    }
+
+    pub fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        crate::repr_fields("NacosConfigResponse", &self.to_dict(py)?)
+    }
+
+    pub fn __eq__(&self, other: PyRef<'_, Self>) -> bool {
+        *self == *other
+    }
+
+    pub fn __hash__(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        std::hash::Hash::hash(self, &mut hasher);
+        std::hash::Hasher::finish(&hasher)
+    }
+
+    pub fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    /// Support pickle, e.g. send to multiprocessing workers.
+    pub fn __reduce__<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<(Bound<'py, PyAny>, (Bound<'py, PyDict>,))> {
+        let from_dict = py.get_type::<Self>().getattr("from_dict")?;
+        Ok((from_dict, (self.to_dict(py)?,)))
+    }
 }
 
 pub(crate) struct NacosConfigChangeListener {
     pub(crate) func: Arc<Py<PyAny>>,
+    pub(crate) failover: Arc<ConfigFailover>,
+    pub(crate) spans: ListenerSpans,
+}
+
+impl NacosConfigChangeListener {
+    pub(crate) fn notify_resp(&self, ffi_conf_resp: NacosConfigResponse) {
+        // call PyFunction with args
+        let _ = Python::attach(|py| -> PyResult<()> {
+            let started = Instant::now();
+            let result = self.spans.call(py, &self.func, (ffi_conf_resp,));
+            crate::metrics::observe_listener("config", started.elapsed(), result.is_err());
+            Ok(())
+        });
+    }
 }
 
 impl nacos_sdk::api::config::ConfigChangeListener for NacosConfigChangeListener {
     fn notify(&self, config_resp: nacos_sdk::api::config::ConfigResponse) {
         let ffi_conf_resp = transfer_conf_resp(config_resp);
+        // the config removed is notified with empty content, which is not a copy to serve
+        if ffi_conf_resp.content.is_empty() {
+            self.failover
+                .remove(&ffi_conf_resp.data_id, &ffi_conf_resp.group);
+        } else {
+            self.failover.save(
+                &ffi_conf_resp.data_id,
+                &ffi_conf_resp.group,
+                &ffi_conf_resp.content,
+                &ffi_conf_resp.content_type,
+            );
+        }
+        self.notify_resp(ffi_conf_resp);
+    }
+}
+
+/// Notified once when the config appears, for `wait_for_config`.
+struct ConfigAppearListener {
+    sender: Mutex<Option<tokio::sync::oneshot::Sender<NacosConfigResponse>>>,
+    failover: Arc<ConfigFailover>,
+}
+
+impl nacos_sdk::api::config::ConfigChangeListener for ConfigAppearListener {
+    fn notify(&self, config_resp: nacos_sdk::api::config::ConfigResponse) {
+        // the config removed is notified with empty content
+        if config_resp.content().is_empty() {
+            return;
+        }
+        let ffi_conf_resp = transfer_conf_resp(config_resp);
+        self.failover.save(
+            &ffi_conf_resp.data_id,
+            &ffi_conf_resp.group,
+            &ffi_conf_resp.content,
+            &ffi_conf_resp.content_type,
+        );
+        if let Some(sender) = self.sender.lock().ok().and_then(|mut sender| sender.take()) {
+            let _ = sender.send(ffi_conf_resp);
+        }
+    }
+}
 
-        // call PyFunction with args
-        let _ = Python::attach(|py| -> PyResult<()> {
-            let _ = self.func.call(py, (ffi_conf_resp,), None);
-            Ok(())
+/// Get the configs concurrently on the runtime, the results are per (data_id, group).
+pub(crate) async fn get_configs(
+    config_service: nacos_sdk::api::config::ConfigService,
+    failover: Arc<ConfigFailover>,
+    keys: Vec<(String, String)>,
+    max_concurrency: Option<usize>,
+) -> PyResult<HashMap<(String, String), PyResult<NacosConfigResponse>>> {
+    let max_concurrency = max_concurrency.unwrap_or(DEFAULT_GET_CONFIGS_CONCURRENCY);
+    if max_concurrency == 0 {
+        return Err(PyErr::new::<PyValueError, _>(
+            "Arg `max_concurrency` must be positive",
+        ));
+    }
+
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(max_concurrency));
+    let mut tasks = tokio::task::JoinSet::new();
+    let keys: std::collections::HashSet<(String, String)> = keys.into_iter().collect();
+    for (data_id, group) in keys {
+        let config_service = config_service.clone();
+        let failover = failover.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let result = failover
+                .get_config(
+                    "get_configs",
+                    &config_service,
+                    data_id.clone(),
+                    group.clone(),
+                )
+                .await;
+            ((data_id, group), result)
         });
     }
+
+    let mut results = HashMap::with_capacity(tasks.len());
+    while let Some(joined) = tasks.join_next().await {
+        let (key, result) =
+            joined.map_err(|join_err| PyRuntimeError::new_err(format!("{:?}", &join_err)))?;
+        results.insert(key, result);
+    }
+    Ok(results)
+}
+
+/// Convert the results of `get_configs` to dict, the value is the exception when it fails.
+pub(crate) fn into_py_dict<'py>(
+    py: Python<'py>,
+    results: HashMap<(String, String), PyResult<NacosConfigResponse>>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    for (key, result) in results {
+        match result {
+            Ok(config_resp) => dict.set_item(key, config_resp)?,
+            Err(err) => dict.set_item(key, err)?,
+        }
+    }
+    Ok(dict)
+}
+
+/// Wait until the config appears, by a listener which is removed at last.
+pub(crate) async fn wait_for_config(
+    config_service: nacos_sdk::api::config::ConfigService,
+    failover: Arc<ConfigFailover>,
+    data_id: String,
+    group: String,
+    timeout: Duration,
+) -> PyResult<NacosConfigResponse> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let listener = Arc::new(ConfigAppearListener {
+        sender: Mutex::new(Some(sender)),
+        failover: failover.clone(),
+    });
+    // listen before get, so the config published in between is not missed
+    config_service
+        .add_listener(data_id.clone(), group.clone(), listener.clone())
+        .await
+        .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+
+    let result = match failover
+        .fetch(
+            "wait_for_config",
+            &config_service,
+            data_id.clone(),
+            group.clone(),
+        )
+        .await
+    {
+        Ok(config_resp) => Ok(config_resp),
+        Err(nacos_sdk::api::error::Error::ConfigNotFound(_)) => {
+            match tokio::time::timeout(timeout, receiver).await {
+                Ok(Ok(config_resp)) => Ok(config_resp),
+                _ => Err(PyTimeoutError::new_err(format!(
+                    "Config `{}` of group `{}` did not appear in {:?}",
+                    data_id, group, timeout
+                ))),
+            }
+        }
+        Err(nacos_err) => Err(PyRuntimeError::new_err(format!("{:?}", &nacos_err))),
+    };
+
+    let _ = config_service
+        .remove_listener(data_id, group, listener)
+        .await;
+    result
+}
+
+/// Fill the metadata of config which the SDK does not expose, by the open api of Nacos server:
+/// the detail of config for last_modified, encrypted_data_key and tags, and its beta release.
+pub(crate) async fn fill_metadata(
+    open_api: &OpenApi,
+    config_resp: &mut NacosConfigResponse,
+) -> PyResult<()> {
+    let query = [
+        ("dataId", config_resp.data_id.as_str()),
+        ("group", config_resp.group.as_str()),
+        ("tenant", config_resp.namespace.as_str()),
+    ];
+    let get_json = |extra: (&'static str, &'static str)| async move {
+        open_api
+            .get("/v1/cs/configs")
+            .await?
+            .query(&query)
+            .query(&[extra])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|http_err| PyRuntimeError::new_err(format!("{:?}", &http_err)))?
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|http_err| PyRuntimeError::new_err(format!("{:?}", &http_err)))
+    };
+
+    let detail = get_json(("show", "all")).await?;
+    config_resp.last_modified = detail["modifyTime"].as_i64();
+    config_resp.encrypted_data_key = detail["encryptedDataKey"]
+        .as_str()
+        .filter(|key| !key.is_empty())
+        .map(str::to_string);
+    config_resp.tags = detail["configTags"]
+        .as_str()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    // the data is null without beta release
+    let beta = get_json(("beta", "true")).await?;
+    config_resp.beta = !beta["data"].is_null();
+    Ok(())
+}
+
+impl NacosConfigResponse {
+    /// Content as bytes, see `content_bytes`.
+    pub(crate) fn bytes(&self) -> PyResult<Vec<u8>> {
+        if self.content_type != BINARY_CONTENT_TYPE {
+            return Ok(self.content.clone().into_bytes());
+        }
+        base64::engine::general_purpose::STANDARD
+            .decode(self.content.trim())
+            .map_err(|decode_err| {
+                PyErr::new::<PyValueError, _>(format!(
+                    "Content of config `{}` of group `{}` is not base64: {:?}",
+                    self.data_id, self.group, decode_err
+                ))
+            })
+    }
+}
+
+/// Content of `publish_config_bytes`, the base64 of bytes.
+pub(crate) fn encode_bytes(content: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(content)
 }
 
 pub(crate) fn transfer_conf_resp(
@@ -195,5 +788,11 @@ pub(crate) fn transfer_conf_resp(
         content: config_resp.content().to_string(),
         content_type: config_resp.content_type().to_string(),
         md5: config_resp.md5().to_string(),
+        from_failover: false,
+        // not exposed by the SDK, see `fill_metadata`
+        last_modified: None,
+        encrypted_data_key: None,
+        beta: false,
+        tags: Vec::new(),
     }
 }
@@ -0,0 +1,389 @@
+#![deny(clippy::all)]
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::{Bound, IntoPyObject, IntoPyObjectExt, PyAny, PyErr, PyResult, Python};
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+use nacos_sdk::api::config::ConfigService;
+use serde::{Deserialize, Serialize};
+
+use crate::connection::ConnectionTracker;
+use crate::open_api::OpenApi;
+
+/// Page size of listing configs.
+const PAGE_SIZE: u64 = 500;
+/// Metadata of the exported configs, the same as the export(v2) of Nacos console.
+const METADATA_FILE: &str = ".metadata.yml";
+
+/// What to do when the imported config exists.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum ImportPolicy {
+    /// Keep the existing config
+    Skip,
+    /// Publish the imported config
+    Overwrite,
+    /// Import nothing if any config exists
+    Abort,
+}
+
+impl ImportPolicy {
+    pub(crate) fn parse(policy: Option<String>) -> PyResult<Self> {
+        match policy.as_deref() {
+            None | Some("skip") => Ok(ImportPolicy::Skip),
+            Some("overwrite") => Ok(ImportPolicy::Overwrite),
+            Some("abort") => Ok(ImportPolicy::Abort),
+            Some(other) => Err(PyErr::new::<PyValueError, _>(format!(
+                "Unknown policy `{}`, must be one of skip, overwrite, abort",
+                other
+            ))),
+        }
+    }
+}
+
+/// Result of importing a config, it is a str in Python, or the err when it fails.
+pub enum ImportStatus {
+    Created,
+    Overwritten,
+    Skipped,
+    /// exists, and nothing is imported by policy abort
+    Conflict,
+    /// not exists, but nothing is imported by policy abort
+    Aborted,
+    Failed(PyErr),
+}
+
+impl<'py> IntoPyObject<'py> for ImportStatus {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        let status = match self {
+            ImportStatus::Created => "created",
+            ImportStatus::Overwritten => "overwritten",
+            ImportStatus::Skipped => "skipped",
+            ImportStatus::Conflict => "conflict",
+            ImportStatus::Aborted => "aborted",
+            ImportStatus::Failed(err) => return err.into_bound_py_any(py),
+        };
+        status.into_bound_py_any(py)
+    }
+}
+
+struct ExportedConfig {
+    data_id: String,
+    group: String,
+    content: String,
+    content_type: Option<String>,
+    app_name: Option<String>,
+}
+
+/// `.metadata.yml`, e.g.
+/// ```yaml
+/// metadata:
+/// - dataId: app.yaml
+///   group: DEFAULT_GROUP
+///   type: yaml
+///   appName: app
+///   md5: 0f3c7ba5e0a0b0ba3e1a1f1c1e0b4b1e
+/// ```
+/// md5 is an extra field of the console's, checked on import when present.
+#[derive(Serialize, Deserialize, Default)]
+struct Metadata {
+    #[serde(default)]
+    metadata: Vec<MetadataItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetadataItem {
+    data_id: String,
+    group: String,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    app_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    md5: Option<String>,
+}
+
+/// Export the configs of a namespace by the open api of Nacos server, since the SDK could not list configs.
+pub(crate) struct ConfigExporter {
+    open_api: Arc<OpenApi>,
+}
+
+impl ConfigExporter {
+    pub(crate) fn new(open_api: Arc<OpenApi>) -> Self {
+        Self { open_api }
+    }
+
+    async fn list_configs(
+        &self,
+        namespace: &str,
+        group: Option<&str>,
+    ) -> PyResult<Vec<ExportedConfig>> {
+        let mut configs = Vec::new();
+        let mut page_no: u64 = 1;
+        let page_size = PAGE_SIZE.to_string();
+        loop {
+            let page_no_str = page_no.to_string();
+            let page: serde_json::Value = self
+                .open_api
+                .get("/v1/cs/configs")
+                .await?
+                .query(&[
+                    ("search", "blur"),
+                    ("dataId", ""),
+                    ("group", group.unwrap_or_default()),
+                    ("tenant", namespace),
+                    ("pageNo", page_no_str.as_str()),
+                    ("pageSize", page_size.as_str()),
+                ])
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(|http_err| PyRuntimeError::new_err(format!("{:?}", &http_err)))?
+                .json()
+                .await
+                .map_err(|http_err| PyRuntimeError::new_err(format!("{:?}", &http_err)))?;
+
+            for item in page["pageItems"].as_array().into_iter().flatten() {
+                let (Some(data_id), Some(group)) =
+                    (item["dataId"].as_str(), item["group"].as_str())
+                else {
+                    continue;
+                };
+                configs.push(ExportedConfig {
+                    data_id: data_id.to_string(),
+                    group: group.to_string(),
+                    content: item["content"].as_str().unwrap_or_default().to_string(),
+                    content_type: item["type"].as_str().map(str::to_string),
+                    app_name: item["appName"]
+                        .as_str()
+                        .filter(|app_name| !app_name.is_empty())
+                        .map(str::to_string),
+                });
+            }
+
+            if page_no >= page["pagesAvailable"].as_u64().unwrap_or_default() {
+                break;
+            }
+            page_no += 1;
+        }
+        Ok(configs)
+    }
+
+    /// Export the configs of namespace (and group) to a zip, returns the count of them.
+    /// The zip is written to a temporary file beside path then renamed, so path is never partial.
+    pub(crate) async fn export(
+        &self,
+        namespace: String,
+        group: Option<String>,
+        path: String,
+    ) -> PyResult<usize> {
+        let configs = self.list_configs(&namespace, group.as_deref()).await?;
+        let count = configs.len();
+
+        crate::runtime()
+            .spawn_blocking(move || {
+                let temp_path = format!("{}.{}.tmp", path, std::process::id());
+                let written = write_zip(&temp_path, &configs)
+                    .and_then(|()| std::fs::rename(&temp_path, &path).map_err(PyErr::from));
+                if written.is_err() {
+                    let _ = std::fs::remove_file(&temp_path);
+                }
+                written
+            })
+            .await
+            .map_err(|join_err| PyRuntimeError::new_err(format!("{:?}", &join_err)))??;
+        Ok(count)
+    }
+}
+
+fn write_zip(path: &str, configs: &[ExportedConfig]) -> PyResult<()> {
+    let mut metadata = Metadata::default();
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path)?);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for config in configs {
+        let name = format!("{}/{}", config.group, config.data_id);
+        zip.start_file(name, options).map_err(zip_err)?;
+        zip.write_all(config.content.as_bytes())?;
+
+        metadata.metadata.push(MetadataItem {
+            data_id: config.data_id.clone(),
+            group: config.group.clone(),
+            content_type: config.content_type.clone(),
+            app_name: config.app_name.clone(),
+            md5: Some(format!("{:x}", md5::compute(&config.content))),
+        });
+    }
+    let metadata = serde_yaml_ng::to_string(&metadata)
+        .map_err(|yaml_err| PyRuntimeError::new_err(format!("{:?}", &yaml_err)))?;
+    zip.start_file(METADATA_FILE, options).map_err(zip_err)?;
+    zip.write_all(metadata.as_bytes())?;
+    zip.finish().map_err(zip_err)?.sync_all()?;
+    Ok(())
+}
+
+/// A config in the zip, (data_id, group, content).
+type ZipEntry = (String, String, Vec<u8>);
+
+/// The configs of the zip, and the content of `.metadata.yml`.
+fn read_zip(path: &str) -> PyResult<(Vec<ZipEntry>, String)> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?).map_err(zip_err)?;
+    let mut metadata = String::new();
+    let mut entries = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(zip_err)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        match name.as_str() {
+            METADATA_FILE => metadata = String::from_utf8_lossy(&content).into_owned(),
+            // group/data_id, other files are not configs
+            _ => {
+                if let Some((group, data_id)) = name.split_once('/')
+                    && !group.is_empty()
+                    && !data_id.is_empty()
+                    && !data_id.contains('/')
+                {
+                    entries.push((data_id.to_string(), group.to_string(), content));
+                }
+            }
+        }
+    }
+    Ok((entries, metadata))
+}
+
+/// Import the configs of the zip, which is exported by `export_configs` or Nacos console (v2),
+/// into the namespace of the client. Returns the result of each (data_id, group).
+pub(crate) async fn import_configs(
+    config_service: &ConfigService,
+    connection: &ConnectionTracker,
+    path: String,
+    policy: ImportPolicy,
+) -> PyResult<HashMap<(String, String), ImportStatus>> {
+    let (entries, metadata) = crate::runtime()
+        .spawn_blocking(move || read_zip(&path))
+        .await
+        .map_err(|join_err| PyRuntimeError::new_err(format!("{:?}", &join_err)))??;
+    let mut metadata = parse_metadata(&metadata)?;
+
+    // check first, so that nothing is imported by policy abort
+    let mut checked = Vec::with_capacity(entries.len());
+    let mut results = HashMap::new();
+    for (data_id, group, content) in entries {
+        let key = (data_id.clone(), group.clone());
+        let name = format!("{}/{}", group, data_id);
+        let content = match String::from_utf8(content) {
+            Ok(content) => content,
+            Err(_) => {
+                let err = PyErr::new::<PyValueError, _>(format!("`{}` is not UTF-8", name));
+                results.insert(key, ImportStatus::Failed(err));
+                continue;
+            }
+        };
+        if let Some(md5) = metadata.get(&key).and_then(|item| item.md5.as_deref())
+            && !md5.eq_ignore_ascii_case(&format!("{:x}", md5::compute(&content)))
+        {
+            let err = PyErr::new::<PyValueError, _>(format!(
+                "md5 of `{}` does not match the metadata",
+                name
+            ));
+            results.insert(key, ImportStatus::Failed(err));
+            continue;
+        }
+        let exists = match connection
+            .track(
+                "import_configs",
+                config_service.get_config(data_id.clone(), group.clone()),
+            )
+            .await
+        {
+            Ok(_) => true,
+            Err(nacos_sdk::api::error::Error::ConfigNotFound(_)) => false,
+            Err(nacos_err) => {
+                let err = PyRuntimeError::new_err(format!("{:?}", &nacos_err));
+                results.insert(key, ImportStatus::Failed(err));
+                continue;
+            }
+        };
+        checked.push((data_id, group, content, exists));
+    }
+
+    if policy == ImportPolicy::Abort && checked.iter().any(|(_, _, _, exists)| *exists) {
+        for (data_id, group, _, exists) in checked {
+            let status = if exists {
+                ImportStatus::Conflict
+            } else {
+                ImportStatus::Aborted
+            };
+            results.insert((data_id, group), status);
+        }
+        return Ok(results);
+    }
+
+    for (data_id, group, content, exists) in checked {
+        let key = (data_id.clone(), group.clone());
+        if exists && policy == ImportPolicy::Skip {
+            results.insert(key, ImportStatus::Skipped);
+            continue;
+        }
+        let (content_type, app_name) = match metadata.remove(&key) {
+            Some(item) => (item.content_type, item.app_name),
+            None => (None, None),
+        };
+        // appName is an addition param of the publish request, which the server keeps
+        let params = app_name
+            .map(|app_name| HashMap::from([("appName".to_string(), app_name)]))
+            .unwrap_or_default();
+        let status = match connection
+            .track(
+                "import_configs",
+                config_service.publish_config_param(
+                    data_id,
+                    group,
+                    content,
+                    content_type,
+                    None,
+                    params,
+                ),
+            )
+            .await
+        {
+            Ok(_) if exists => ImportStatus::Overwritten,
+            Ok(_) => ImportStatus::Created,
+            Err(nacos_err) => {
+                ImportStatus::Failed(PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+            }
+        };
+        results.insert(key, status);
+    }
+    Ok(results)
+}
+
+fn zip_err(zip_err: zip::result::ZipError) -> PyErr {
+    PyErr::new::<PyValueError, _>(format!("Invalid zip, {}", zip_err))
+}
+
+/// The item of each (data_id, group) in `.metadata.yml`, empty when there is no metadata.
+fn parse_metadata(metadata: &str) -> PyResult<HashMap<(String, String), MetadataItem>> {
+    if metadata.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+    let metadata: Metadata = serde_yaml_ng::from_str(metadata).map_err(|yaml_err| {
+        PyErr::new::<PyValueError, _>(format!("Invalid {}, {}", METADATA_FILE, yaml_err))
+    })?;
+    Ok(metadata
+        .metadata
+        .into_iter()
+        .map(|item| ((item.data_id.clone(), item.group.clone()), item))
+        .collect())
+}
@@ -0,0 +1,412 @@
+#![deny(clippy::all)]
+
+use pyo3::types::{PyDict, PyDictMethods};
+use pyo3::{Bound, Py, PyAny, PyResult, Python, pyclass, pymethods};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Sender, channel};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, Instant};
+
+use nacos_sdk::api::error::Error;
+
+/// Interval of probing the connection by a request to server, while the state is observed.
+const PROBE_INTERVAL: Duration = Duration::from_secs(5);
+/// Default timeout of health_check, 3s
+pub(crate) const DEFAULT_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+/// Error code of the response when the auth fails, `NO_RIGHT` of Nacos server
+const NO_RIGHT: i32 = 403;
+
+/// A request to server, for probing the connection.
+pub(crate) type Probe = Box<
+    dyn Fn() -> Pin<Box<dyn Future<Output = nacos_sdk::api::error::Result<()>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Probe of the config client, the config not found is also answered by server.
+pub(crate) fn config_probe(config_service: nacos_sdk::api::config::ConfigService) -> Probe {
+    Box::new(move || {
+        let config_service = config_service.clone();
+        Box::pin(async move {
+            config_service
+                .get_config(
+                    "nacos-binding-connection-probe".to_string(),
+                    "DEFAULT_GROUP".to_string(),
+                )
+                .await
+                .map(|_| ())
+        })
+    })
+}
+
+/// Probe of the naming client, by the first page of the service list with one service.
+pub(crate) fn naming_probe(naming_service: nacos_sdk::api::naming::NamingService) -> Probe {
+    Box::new(move || {
+        let naming_service = naming_service.clone();
+        Box::pin(async move {
+            naming_service
+                .get_service_list(1, 1, None)
+                .await
+                .map(|_| ())
+        })
+    })
+}
+
+/// Whether a request was answered by server, also with an err.
+fn answered<T>(result: &nacos_sdk::api::error::Result<T>) -> bool {
+    matches!(
+        result,
+        Ok(_)
+            | Err(Error::ErrResponse(..))
+            | Err(Error::ConfigNotFound(_))
+            | Err(Error::ConfigQueryConflict(_))
+    )
+}
+
+/// State of the connection between the client and server.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum ConnectionState {
+    /// No request has been answered yet
+    Connecting,
+    /// The last request was answered by server
+    Connected,
+    /// The last request was not answered, e.g. no server is available, timeout or transport error
+    Disconnected,
+    /// The client is closed
+    Shutdown,
+}
+
+impl ConnectionState {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionState::Connecting => "connecting",
+            ConnectionState::Connected => "connected",
+            ConnectionState::Disconnected => "disconnected",
+            ConnectionState::Shutdown => "shutdown",
+        }
+    }
+}
+
+/// Tracker of the connection state of a client. nacos-sdk does not expose the events of its gRPC
+/// connection, so the state is observed from the results of the requests of this client, and of
+/// the probe requests on an interval while the state is observed and the client is idle.
+pub(crate) struct ConnectionTracker {
+    state: Mutex<ConnectionState>,
+    /// When a request was answered by server last time
+    last_answered: Mutex<Option<Instant>>,
+    /// The only server of server_addr, None with several servers or endpoint
+    server_addr: Option<String>,
+    /// server_addr or endpoint as configured
+    configured_addr: String,
+    /// Whether an auth plugin is enabled, by username & password or access_key & access_secret
+    auth_enabled: bool,
+    callbacks: Arc<Mutex<Vec<Py<PyAny>>>>,
+    /// Sender to the thread calling the callbacks, spawned at the first callback added
+    notifier: Mutex<Option<Sender<ConnectionState>>>,
+    /// Set after the service of client is built
+    probe: OnceLock<Probe>,
+    probing: AtomicBool,
+    /// Dropped to stop probing, when the client is closed or dropped
+    stop_probing: Mutex<Option<tokio::sync::oneshot::Sender<()>>>,
+}
+
+impl ConnectionTracker {
+    pub(crate) fn new(client_options: &crate::ClientOptions) -> Self {
+        let mut servers = client_options
+            .server_addr
+            .split(',')
+            .map(str::trim)
+            .filter(|addr| !addr.is_empty());
+        let server_addr = match (servers.next(), servers.next()) {
+            (Some(addr), None) if client_options.endpoint.is_none() => Some(addr.to_string()),
+            _ => None,
+        };
+        let auth_enabled = matches!(
+            client_options.build_client_props(),
+            Ok((_, crate::AuthPlugin::Http | crate::AuthPlugin::Aliyun))
+        );
+        Self {
+            state: Mutex::new(ConnectionState::Connecting),
+            last_answered: Mutex::new(None),
+            server_addr,
+            configured_addr: client_options
+                .endpoint
+                .clone()
+                .unwrap_or_else(|| client_options.server_addr.clone()),
+            auth_enabled,
+            callbacks: Arc::new(Mutex::new(Vec::new())),
+            notifier: Mutex::new(None),
+            probe: OnceLock::new(),
+            probing: AtomicBool::new(false),
+            stop_probing: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn set_probe(&self, probe: Probe) {
+        let _ = self.probe.set(probe);
+    }
+
+    /// The state is observed, e.g. read or called back, probe the connection on an interval from now,
+    /// so that it changes also when the client is idle. It is a task on the process-wide runtime,
+    /// which ends when the client is closed or dropped.
+    pub(crate) fn observe(self: &Arc<Self>) {
+        if self.probing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let (stop, mut stopped) = tokio::sync::oneshot::channel::<()>();
+        *self.stop_probing.lock().unwrap() = Some(stop);
+        let tracker: Weak<Self> = Arc::downgrade(self);
+        crate::runtime().spawn(async move {
+            // the sender is dropped along with the tracker, or taken at shutdown
+            while tokio::time::timeout(PROBE_INTERVAL, &mut stopped)
+                .await
+                .is_err()
+            {
+                let Some(tracker) = tracker.upgrade() else {
+                    return;
+                };
+                tracker.probe().await;
+            }
+        });
+    }
+
+    /// Probe the connection, unless a request was answered within the interval.
+    async fn probe(&self) {
+        let Some(probe) = self.probe.get() else {
+            return;
+        };
+        let answered_recently = self
+            .last_answered
+            .lock()
+            .unwrap()
+            .is_some_and(|at| at.elapsed() < PROBE_INTERVAL);
+        if answered_recently {
+            return;
+        }
+        // not a request of the user, so it is not in the metrics
+        let result = probe().await;
+        self.observe_result(&result);
+    }
+
+    pub(crate) fn state(&self) -> ConnectionState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Address of the server while connected. nacos-sdk does not expose which server its gRPC
+    /// connection is to, so it is only known when server_addr is a single server.
+    pub(crate) fn server_addr(&self) -> Option<String> {
+        if self.state() == ConnectionState::Connected {
+            self.server_addr.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Observe a request to server, for the connection state and the metrics of method,
+    /// and return its result as is.
+    pub(crate) async fn track<T>(
+        &self,
+        method: &'static str,
+        future: impl Future<Output = nacos_sdk::api::error::Result<T>>,
+    ) -> nacos_sdk::api::error::Result<T> {
+        let started = Instant::now();
+        let result = future.await;
+        crate::metrics::observe_request(
+            method,
+            started.elapsed(),
+            result.as_ref().err().map(crate::metrics::err_kind),
+        );
+        self.observe_result(&result);
+        result
+    }
+
+    /// Update the state by the result of a request to server.
+    fn observe_result<T>(&self, result: &nacos_sdk::api::error::Result<T>) {
+        if answered(result) {
+            *self.last_answered.lock().unwrap() = Some(Instant::now());
+            self.set(ConnectionState::Connected);
+        } else if let Err(Error::ClientShutdown(_)) = result {
+            self.set(ConnectionState::Shutdown);
+        } else {
+            // not answered, e.g. no server is available, timeout or transport error
+            self.set(ConnectionState::Disconnected);
+        }
+    }
+
+    /// Round trip to server by the probe request over the connection of the client, so the auth
+    /// plugin of the client is checked as well. The version of server is best-effort, by the state
+    /// api of the open api. It never fails, the err is in the result.
+    pub(crate) async fn health_check(
+        &self,
+        open_api: &crate::open_api::OpenApi,
+        timeout: Duration,
+    ) -> NacosHealthCheckResult {
+        let Some(probe) = self.probe.get() else {
+            return NacosHealthCheckResult {
+                healthy: false,
+                latency_ms: None,
+                server_addr: self.configured_addr.clone(),
+                server_version: None,
+                auth_status: "unchecked".to_string(),
+                error: Some("The client is not built".to_string()),
+            };
+        };
+        let round_trip = async {
+            let started = Instant::now();
+            let result = tokio::time::timeout(timeout, probe()).await;
+            (result, started.elapsed())
+        };
+        let ((result, latency), server_version) = tokio::join!(
+            round_trip,
+            tokio::time::timeout(timeout, open_api.server_version())
+        );
+
+        let mut health = NacosHealthCheckResult {
+            healthy: false,
+            latency_ms: None,
+            server_addr: String::new(),
+            server_version: server_version.ok().flatten(),
+            auth_status: if self.auth_enabled {
+                "unchecked".to_string()
+            } else {
+                "disabled".to_string()
+            },
+            error: None,
+        };
+        match result {
+            Ok(result) => {
+                // not a request of the user, so it is not in the metrics
+                self.observe_result(&result);
+                if answered(&result) {
+                    health.latency_ms = Some(latency.as_secs_f64() * 1000.0);
+                    if self.auth_enabled {
+                        health.auth_status = "authenticated".to_string();
+                    }
+                }
+                match result {
+                    Ok(()) | Err(Error::ConfigNotFound(_)) | Err(Error::ConfigQueryConflict(_)) => {
+                        health.healthy = true
+                    }
+                    Err(nacos_err @ Error::ErrResponse(_, ret_code, error_code, _))
+                        if ret_code == NO_RIGHT || error_code == NO_RIGHT =>
+                    {
+                        health.auth_status = "failed".to_string();
+                        health.error = Some(format!("{:?}", &nacos_err));
+                    }
+                    Err(nacos_err) => health.error = Some(format!("{:?}", &nacos_err)),
+                }
+            }
+            Err(_) => {
+                self.set(ConnectionState::Disconnected);
+                health.error = Some(format!("Timeout after {:?}", timeout));
+            }
+        }
+        health.server_addr = self
+            .server_addr()
+            .or_else(|| open_api.server_addr())
+            .unwrap_or_else(|| self.configured_addr.clone());
+        health
+    }
+
+    /// The client is closed, its state never changes after that, and the probing stops.
+    pub(crate) fn shutdown(&self) {
+        self.set(ConnectionState::Shutdown);
+        drop(self.stop_probing.lock().unwrap().take());
+    }
+
+    fn set(&self, state: ConnectionState) {
+        {
+            let mut current = self.state.lock().unwrap();
+            if *current == state || *current == ConnectionState::Shutdown {
+                return;
+            }
+            *current = state;
+        }
+        if let Some(notifier) = self.notifier.lock().unwrap().as_ref() {
+            let _ = notifier.send(state);
+        }
+    }
+
+    /// Add the callback of state change, it is called with the new state on a dedicated thread,
+    /// so that the requests never wait for the GIL.
+    pub(crate) fn add_callback(&self, callback: Py<PyAny>) {
+        self.callbacks.lock().unwrap().push(callback);
+
+        let mut notifier = self.notifier.lock().unwrap();
+        if notifier.is_some() {
+            return;
+        }
+        let (sender, receiver) = channel::<ConnectionState>();
+        let callbacks = self.callbacks.clone();
+        // it ends when the tracker is dropped, along with the sender
+        let spawned = std::thread::Builder::new()
+            .name("nacos-connection-notifier".to_string())
+            .spawn(move || {
+                for state in receiver {
+                    Python::attach(|py| {
+                        let callbacks: Vec<Py<PyAny>> = callbacks
+                            .lock()
+                            .unwrap()
+                            .iter()
+                            .map(|callback| callback.clone_ref(py))
+                            .collect();
+                        for callback in callbacks {
+                            // the exception is reported by sys.unraisablehook, the others are still called
+                            if let Err(err) = callback.call1(py, (state.as_str(),)) {
+                                err.write_unraisable(py, Some(callback.bind(py)));
+                            }
+                        }
+                    });
+                }
+            });
+        if spawned.is_ok() {
+            *notifier = Some(sender);
+        }
+    }
+}
+
+/// Result of `health_check` of the clients.
+#[pyclass(module = "nacos_sdk_rust_binding_py")]
+pub struct NacosHealthCheckResult {
+    /// Whether the server is reachable, and the auth passes if it is enabled
+    #[pyo3(get)]
+    pub healthy: bool,
+    /// Latency in milliseconds of the round trip to server, None if it is unreachable
+    #[pyo3(get)]
+    pub latency_ms: Option<f64>,
+    /// Server address checked
+    #[pyo3(get)]
+    pub server_addr: String,
+    /// Version of server, e.g. 2.3.0
+    #[pyo3(get)]
+    pub server_version: Option<String>,
+    /// Auth status: disabled (without username & password or access_key & access_secret),
+    /// authenticated, failed, or unchecked when the server is unreachable
+    #[pyo3(get)]
+    pub auth_status: String,
+    /// Why it is unhealthy
+    #[pyo3(get)]
+    pub error: Option<String>,
+}
+
+#[pymethods]
+impl NacosHealthCheckResult {
+    /// Convert to dict, the keys are the field names, e.g. for the response of readiness probe.
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("healthy", self.healthy)?;
+        dict.set_item("latency_ms", self.latency_ms)?;
+        dict.set_item("server_addr", self.server_addr.clone())?;
+        dict.set_item("server_version", self.server_version.clone())?;
+        dict.set_item("auth_status", self.auth_status.clone())?;
+        dict.set_item("error", self.error.clone())?;
+        Ok(dict)
+    }
+
+    pub fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        crate::repr_fields("NacosHealthCheckResult", &self.to_dict(py)?)
+    }
+}
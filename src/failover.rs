@@ -0,0 +1,190 @@
+#![deny(clippy::all)]
+
+use pyo3::PyResult;
+use pyo3::exceptions::PyRuntimeError;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use nacos_sdk::api::config::ConfigService;
+
+use crate::config::{NacosConfigResponse, transfer_conf_resp};
+use crate::connection::ConnectionTracker;
+
+/// Nacos default namespace `public`, its id is empty.
+const DEFAULT_NAMESPACE: &str = "public";
+/// Content type of the config served from failover, when it is unknown.
+const DEFAULT_CONTENT_TYPE: &str = "text";
+/// Makes the temp file of each save unique, also among the clients of the process.
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Local copies of configs in the failover dir of ClientOptions, `{dir}/{namespace}/{group}/{data_id}`,
+/// with the content type in `{dir}/{namespace}/{group}/.{data_id}.content_type`.
+/// They are updated on every successful fetch, and served when the server is unavailable.
+pub(crate) struct ConfigFailover {
+    dir: Option<PathBuf>,
+    namespace: String,
+    connection: Arc<ConnectionTracker>,
+}
+
+impl ConfigFailover {
+    pub(crate) fn new(
+        client_options: &crate::ClientOptions,
+        connection: Arc<ConnectionTracker>,
+    ) -> Self {
+        Self {
+            dir: client_options
+                .config_failover_dir
+                .as_ref()
+                .map(PathBuf::from),
+            namespace: client_options.namespace.clone(),
+            connection,
+        }
+    }
+
+    fn path(&self, data_id: &str, group: &str) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        let namespace = if self.namespace.is_empty() {
+            DEFAULT_NAMESPACE
+        } else {
+            &self.namespace
+        };
+        // never out of the dir
+        let is_file_name = |name: &str| {
+            !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+        };
+        if !is_file_name(namespace) || !is_file_name(group) || !is_file_name(data_id) {
+            return None;
+        }
+        Some(dir.join(namespace).join(group).join(data_id))
+    }
+
+    fn content_type_path(path: &std::path::Path, data_id: &str) -> PathBuf {
+        path.with_file_name(format!(".{}.content_type", data_id))
+    }
+
+    /// Save the content fetched, it is best-effort and ignores io errors.
+    pub(crate) fn save(&self, data_id: &str, group: &str, content: &str, content_type: &str) {
+        let Some(path) = self.path(data_id, group) else {
+            return;
+        };
+        if let Some(parent) = path.parent()
+            && std::fs::create_dir_all(parent).is_err()
+        {
+            return;
+        }
+        // before the content, so that the content is never served with the stale content type
+        let _ = std::fs::write(Self::content_type_path(&path, data_id), content_type);
+        // write then rename, so that a half-written file is never served
+        let tmp_path = path.with_file_name(format!(
+            ".{}.{}.{}.tmp",
+            data_id,
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        if std::fs::write(&tmp_path, content).is_err() || std::fs::rename(&tmp_path, &path).is_err()
+        {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+    }
+
+    /// Remove the config deleted in server, it is best-effort and ignores io errors.
+    pub(crate) fn remove(&self, data_id: &str, group: &str) {
+        if let Some(path) = self.path(data_id, group) {
+            let _ = std::fs::remove_file(Self::content_type_path(&path, data_id));
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    fn load(&self, data_id: &str, group: &str) -> Option<NacosConfigResponse> {
+        let path = self.path(data_id, group)?;
+        let content = std::fs::read_to_string(&path).ok()?;
+        let content_type = std::fs::read_to_string(Self::content_type_path(&path, data_id))
+            .ok()
+            .filter(|content_type| !content_type.is_empty())
+            .unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_string());
+        Some(NacosConfigResponse {
+            namespace: self.namespace.clone(),
+            data_id: data_id.to_string(),
+            group: group.to_string(),
+            md5: format!("{:x}", md5::compute(content.as_bytes())),
+            content,
+            content_type,
+            from_failover: true,
+            last_modified: None,
+            encrypted_data_key: None,
+            beta: false,
+            tags: Vec::new(),
+        })
+    }
+
+    /// Get config from server and save it, or load it from the failover dir when the server
+    /// is unavailable. The config not found in server is removed from the failover dir.
+    /// The request is observed as the public method calling it.
+    pub(crate) async fn fetch(
+        &self,
+        method: &'static str,
+        config_service: &ConfigService,
+        data_id: String,
+        group: String,
+    ) -> Result<NacosConfigResponse, nacos_sdk::api::error::Error> {
+        let result = self
+            .connection
+            .track(
+                method,
+                config_service.get_config(data_id.clone(), group.clone()),
+            )
+            .await;
+        match result {
+            Ok(config_resp) => {
+                let config_resp = transfer_conf_resp(config_resp);
+                self.save(
+                    &data_id,
+                    &group,
+                    &config_resp.content,
+                    &config_resp.content_type,
+                );
+                Ok(config_resp)
+            }
+            Err(nacos_err @ nacos_sdk::api::error::Error::ConfigNotFound(_)) => {
+                self.remove(&data_id, &group);
+                Err(nacos_err)
+            }
+            Err(nacos_err) => self.load(&data_id, &group).ok_or(nacos_err),
+        }
+    }
+
+    /// Same as `fetch`, with the err of python.
+    pub(crate) async fn get_config(
+        &self,
+        method: &'static str,
+        config_service: &ConfigService,
+        data_id: String,
+        group: String,
+    ) -> PyResult<NacosConfigResponse> {
+        self.fetch(method, config_service, data_id, group)
+            .await
+            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+    }
+
+    /// Get config's content, or the default when the config is not found.
+    /// Other errs are raised even with the default, e.g. the server is unavailable.
+    pub(crate) async fn get_config_or(
+        &self,
+        config_service: &ConfigService,
+        data_id: String,
+        group: String,
+        default: Option<String>,
+    ) -> PyResult<String> {
+        match (
+            self.fetch("get_config", config_service, data_id, group)
+                .await,
+            default,
+        ) {
+            (Ok(config_resp), _) => Ok(config_resp.content),
+            (Err(nacos_sdk::api::error::Error::ConfigNotFound(_)), Some(default)) => Ok(default),
+            (Err(nacos_err), _) => Err(PyRuntimeError::new_err(format!("{:?}", &nacos_err))),
+        }
+    }
+}
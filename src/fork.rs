@@ -0,0 +1,54 @@
+#![deny(clippy::all)]
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Called in the child process by `os.register_at_fork(after_in_child=...)`.
+#[pyfunction]
+fn after_fork_in_child() {
+    crate::logging::after_fork_in_child();
+    crate::metrics::after_fork_in_child();
+    // the clients built after fork use another runtime, the inherited one is never dropped,
+    // which would wait for the threads not existing
+    crate::forget_runtime_after_fork();
+}
+
+/// Register the hook of fork, noop on the platforms without fork, e.g. Windows.
+pub(crate) fn register_at_fork(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let Ok(register_at_fork) = m.py().import("os")?.getattr("register_at_fork") else {
+        return Ok(());
+    };
+    let kwargs = PyDict::new(m.py());
+    kwargs.set_item("after_in_child", wrap_pyfunction!(after_fork_in_child, m)?)?;
+    register_at_fork.call((), Some(&kwargs))?;
+    Ok(())
+}
+
+/// The process which a client is built in. The runtime threads and the gRPC connections of the
+/// client do not exist in the process forked from it, so it never makes progress there.
+#[derive(Clone, Copy)]
+pub(crate) struct BuiltIn {
+    pid: u32,
+}
+
+impl BuiltIn {
+    pub(crate) fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+        }
+    }
+
+    /// Raise instead of hanging, when the client is inherited by fork and does not work in this process.
+    pub(crate) fn check(&self) -> PyResult<()> {
+        if std::process::id() != self.pid {
+            return Err(PyRuntimeError::new_err(format!(
+                "The nacos client was built in process {} before fork, it does not work in this forked process. \
+                 Build the clients after fork, e.g. in the post_fork hook of gunicorn, \
+                 or in the initializer of multiprocessing.Pool",
+                self.pid
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+#![deny(clippy::all)]
+
+use pyo3::exceptions::PyValueError;
+use pyo3::types::PyAnyMethods;
+use pyo3::{Bound, Py, PyAny, PyErr, PyResult, Python};
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use tokio::sync::oneshot::error::TryRecvError;
+
+use crate::registration::Registrations;
+
+/// Interval of health check, default 5s
+const DEFAULT_INTERVAL_MS: u64 = 5_000;
+
+/// What to do with the registered instance when the health check fails.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum FailureAction {
+    /// Re-register the instance with healthy=false
+    Unhealthy,
+    /// Deregister the instance
+    Deregister,
+}
+
+impl FailureAction {
+    pub(crate) fn parse(action: Option<String>) -> PyResult<Self> {
+        match action.as_deref() {
+            None | Some("unhealthy") => Ok(FailureAction::Unhealthy),
+            Some("deregister") => Ok(FailureAction::Deregister),
+            Some(other) => Err(PyErr::new::<PyValueError, _>(format!(
+                "Unknown health_check_failure_action `{}`, must be one of unhealthy, deregister",
+                other
+            ))),
+        }
+    }
+}
+
+/// A Python health-check callable of a registered instance, which returns truthy when healthy.
+pub(crate) struct HealthCheck {
+    pub(crate) func: Py<PyAny>,
+    pub(crate) interval: Duration,
+    /// A call not returned in it is a failure, e.g. the app is deadlocked
+    pub(crate) timeout: Duration,
+    pub(crate) failure_action: FailureAction,
+}
+
+impl HealthCheck {
+    /// Build by the args of register_instance, it is None without health_check.
+    pub(crate) fn parse(
+        func: Option<Bound<'_, PyAny>>,
+        interval_ms: Option<u64>,
+        timeout_ms: Option<u64>,
+        failure_action: Option<String>,
+    ) -> PyResult<Option<Self>> {
+        let Some(func) = func else {
+            return Ok(None);
+        };
+        if !func.is_callable() {
+            return Err(PyErr::new::<PyValueError, _>(
+                "Arg `health_check` must be a callable",
+            ));
+        }
+        let interval = Duration::from_millis(interval_ms.unwrap_or(DEFAULT_INTERVAL_MS).max(1));
+        Ok(Some(Self {
+            func: func.unbind(),
+            interval,
+            timeout: timeout_ms.map(Duration::from_millis).unwrap_or(interval),
+            failure_action: FailureAction::parse(failure_action)?,
+        }))
+    }
+}
+
+/// Calls the PyFunction on a blocking thread of the shared runtime, and waits for it with timeout.
+/// A call never returned keeps its thread forever, since python could not be interrupted,
+/// so no call is started while the last one is running, and the checks are failures until it returns.
+struct HealthCheckCaller {
+    func: Arc<Py<PyAny>>,
+    /// The last call which timed out, still running
+    calling: Option<tokio::task::JoinHandle<bool>>,
+    timeout: Duration,
+}
+
+impl HealthCheckCaller {
+    /// Call the PyFunction, or wait for the last call if it is still running.
+    async fn is_healthy(&mut self) -> bool {
+        let mut call = match self.calling.take() {
+            Some(call) => call,
+            None => {
+                let func = self.func.clone();
+                crate::runtime().spawn_blocking(move || {
+                    // an exception is treated as failure
+                    Python::attach(|py| {
+                        func.call0(py)
+                            .and_then(|result| result.bind(py).is_truthy())
+                            .unwrap_or(false)
+                    })
+                })
+            }
+        };
+        match tokio::time::timeout(self.timeout, &mut call).await {
+            Ok(healthy) => healthy.unwrap_or(false),
+            Err(_) => {
+                self.calling = Some(call);
+                false
+            }
+        }
+    }
+}
+
+struct Checker {
+    /// Dropped to stop the checker
+    stop: tokio::sync::oneshot::Sender<()>,
+    failing: Arc<AtomicBool>,
+    failure_action: FailureAction,
+}
+
+/// The running health checkers of the instances registered by a naming client, each is a task
+/// on the process-wide runtime, which is stopped by deregister or drop.
+#[derive(Default)]
+pub(crate) struct HealthCheckers {
+    checkers: Mutex<HashMap<String, Checker>>,
+}
+
+impl HealthCheckers {
+    /// Start the checker of the owned instance, on failure and restore the owned instances of its
+    /// service are published again by the registrations, see `Registrations::publish`.
+    pub(crate) fn start(
+        &self,
+        registrations: Weak<Registrations>,
+        service_name: String,
+        group: String,
+        ip: &str,
+        port: i32,
+        health_check: HealthCheck,
+    ) {
+        let key = registration_key(&service_name, &group, ip, port);
+        let (stop, mut stopped) = tokio::sync::oneshot::channel::<()>();
+        let failing = Arc::new(AtomicBool::new(false));
+        let failure_action = health_check.failure_action;
+
+        let mut caller = HealthCheckCaller {
+            func: Arc::new(health_check.func),
+            calling: None,
+            timeout: health_check.timeout,
+        };
+        let task_key = key.clone();
+        let task_failing = failing.clone();
+        crate::runtime().spawn(async move {
+            let mut healthy = true;
+            // the sender is dropped when the checker stops
+            while tokio::time::timeout(health_check.interval, &mut stopped)
+                .await
+                .is_err()
+            {
+                let check_healthy = caller.is_healthy().await;
+                let stopping = matches!(stopped.try_recv(), Err(TryRecvError::Closed));
+                if check_healthy == healthy || stopping {
+                    continue;
+                }
+                let Some(registrations) = registrations.upgrade() else {
+                    return;
+                };
+
+                // published unhealthy or left out while failing, restored when it passes again
+                task_failing.store(!check_healthy, Ordering::Release);
+                let published = registrations
+                    .republish(&service_name, &group, &task_key)
+                    .await;
+                if published.is_ok() {
+                    healthy = check_healthy;
+                } else {
+                    // retry at the next interval
+                    task_failing.store(!healthy, Ordering::Release);
+                }
+            }
+        });
+
+        let checker = Checker {
+            stop,
+            failing,
+            failure_action,
+        };
+        if let Some(old) = self.checkers.lock().unwrap().insert(key, checker) {
+            old.stop();
+        }
+    }
+
+    pub(crate) fn stop(&self, service_name: &str, group: &str, ip: &str, port: i32) {
+        let key = registration_key(service_name, group, ip, port);
+        if let Some(checker) = self.checkers.lock().unwrap().remove(&key) {
+            checker.stop();
+        }
+    }
+
+    pub(crate) fn stop_all(&self) {
+        for (_, checker) in self.checkers.lock().unwrap().drain() {
+            checker.stop();
+        }
+    }
+
+    /// The failure action of the instance if its health check is failing, by the key of `registration_key`.
+    pub(crate) fn failure_action(&self, key: &str) -> Option<FailureAction> {
+        self.checkers
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|checker| checker.failing.load(Ordering::Acquire))
+            .map(|checker| checker.failure_action)
+    }
+}
+
+impl Drop for HealthCheckers {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+impl Checker {
+    fn stop(self) {
+        drop(self.stop);
+    }
+}
+
+/// The key of a registered instance, group@@service_name@@ip:port
+pub(crate) fn registration_key(service_name: &str, group: &str, ip: &str, port: i32) -> String {
+    format!("{}@@{}@@{}:{}", group, service_name, ip, port)
+}
@@ -1,28 +1,198 @@
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
-use std::cell::OnceCell;
+use pyo3::types::{PyDict, PyTuple, PyType};
+use pyo3::{FromPyObject, IntoPyObjectExt};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicPtr, Ordering};
 
-// Thread-local Tokio runtime for blocking operations.
-// Each OS thread has its own runtime to avoid contention between Python threads.
-thread_local! {
-    static RT: OnceCell<tokio::runtime::Runtime> = const { OnceCell::new() };
+// Process-wide Tokio runtime, shared by the sync and async clients.
+// Its worker threads drive the gRPC connections, push and heartbeat, while Python is idle.
+// The child process of fork forgets it, because its threads do not exist there, and builds another.
+// A runtime is never dropped, which would wait for the threads.
+static RT: AtomicPtr<tokio::runtime::Runtime> = AtomicPtr::new(std::ptr::null_mut());
+
+fn build_runtime(worker_threads: Option<usize>) -> std::io::Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all().thread_name("nacos-binding-rt");
+    if let Some(worker_threads) = worker_threads {
+        builder.worker_threads(worker_threads);
+    }
+    builder.build()
+}
+
+/// Set the runtime if there is none, otherwise give it back.
+fn set_runtime(
+    runtime: tokio::runtime::Runtime,
+) -> Result<&'static tokio::runtime::Runtime, tokio::runtime::Runtime> {
+    let new = Box::into_raw(Box::new(runtime));
+    match RT.compare_exchange(
+        std::ptr::null_mut(),
+        new,
+        Ordering::AcqRel,
+        Ordering::Acquire,
+    ) {
+        // SAFETY: it is leaked, so it lives as long as the process
+        Ok(_) => Ok(unsafe { &*new }),
+        // SAFETY: it is not shared, since it is not set
+        Err(_) => Err(*unsafe { Box::from_raw(new) }),
+    }
+}
+
+/// Get the process-wide runtime, which is built with the default worker count at first use.
+pub(crate) fn runtime() -> &'static tokio::runtime::Runtime {
+    let current = RT.load(Ordering::Acquire);
+    if !current.is_null() {
+        // SAFETY: the runtime set is leaked, so it lives as long as the process
+        return unsafe { &*current };
+    }
+    let runtime = build_runtime(None).expect("Failed to create Tokio multi-thread runtime");
+    match set_runtime(runtime) {
+        Ok(runtime) => runtime,
+        // built by another thread meanwhile
+        Err(runtime) => {
+            runtime.shutdown_background();
+            // SAFETY: the runtime set is leaked, so it lives as long as the process
+            unsafe { &*RT.load(Ordering::Acquire) }
+        }
+    }
+}
+
+/// Forget the runtime in the child process of fork, the next use builds another.
+/// The inherited runtime is leaked on purpose: its worker threads do not exist in the child,
+/// so dropping or shutting it down would wait for them forever. Its memory is the only cost,
+/// once per fork.
+pub(crate) fn forget_runtime_after_fork() {
+    RT.store(std::ptr::null_mut(), Ordering::Release);
+}
+
+tokio::task_local! {
+    static TASK_LOCALS: std::cell::OnceCell<pyo3_async_runtimes::TaskLocals>;
+}
+
+/// The runtime of the async clients, which spawns on the current process-wide runtime, rather than
+/// the one of `pyo3_async_runtimes::tokio` that is fixed for the process, also in the child of fork.
+struct AsyncRuntime;
+
+impl pyo3_async_runtimes::generic::Runtime for AsyncRuntime {
+    type JoinError = tokio::task::JoinError;
+    type JoinHandle = tokio::task::JoinHandle<()>;
+
+    fn spawn<F>(fut: F) -> Self::JoinHandle
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        runtime().spawn(fut)
+    }
+
+    fn spawn_blocking<F>(f: F) -> Self::JoinHandle
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        runtime().spawn_blocking(f)
+    }
 }
 
-/// Block on a future using the thread-local Tokio runtime.
+impl pyo3_async_runtimes::generic::ContextExt for AsyncRuntime {
+    fn scope<F, R>(
+        locals: pyo3_async_runtimes::TaskLocals,
+        fut: F,
+    ) -> Pin<Box<dyn std::future::Future<Output = R> + Send>>
+    where
+        F: std::future::Future<Output = R> + Send + 'static,
+    {
+        let cell = std::cell::OnceCell::new();
+        let _ = cell.set(locals);
+        Box::pin(TASK_LOCALS.scope(cell, fut))
+    }
+
+    fn get_task_locals() -> Option<pyo3_async_runtimes::TaskLocals> {
+        TASK_LOCALS
+            .try_with(|cell| cell.get().cloned())
+            .unwrap_or_default()
+    }
+}
+
+/// Convert the future to a python awaitable of the async clients, it runs on the process-wide runtime.
+pub(crate) fn future_into_py<F, T>(py: Python<'_>, future: F) -> PyResult<Bound<'_, PyAny>>
+where
+    F: std::future::Future<Output = PyResult<T>> + Send + 'static,
+    T: for<'py> IntoPyObject<'py> + Send + 'static,
+{
+    pyo3_async_runtimes::generic::future_into_py::<AsyncRuntime, F, T>(py, future)
+}
+
+/// Block on a future using the process-wide Tokio runtime.
+/// Call it with the GIL released by `py.detach` when the GIL is held, the runtime workers attach
+/// to python, e.g. to notify listeners and forward logs, which deadlocks while the GIL is held.
 pub fn block_on<F>(future: F) -> F::Output
 where
     F: std::future::Future,
 {
-    RT.with(|rt| {
-        let runtime = rt.get_or_init(|| {
-            tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .expect("Failed to create Tokio current-thread runtime")
-        });
-        runtime.block_on(future)
+    runtime().block_on(future)
+}
+
+/// Python-like repr of the fields, e.g. `ClassName(field='value', ...)`.
+pub(crate) fn repr_fields(class_name: &str, fields: &Bound<'_, PyDict>) -> PyResult<String> {
+    let fields = fields
+        .iter()
+        .map(|(key, value)| Ok(format!("{}={}", key.str()?, value.repr()?)))
+        .collect::<PyResult<Vec<String>>>()?;
+    Ok(format!("{}({})", class_name, fields.join(", ")))
+}
+
+/// Get the value of key from dict, a missing key or `None` value is treated as `None`.
+pub(crate) fn extract_dict_item<'py, T>(dict: &Bound<'py, PyDict>, key: &str) -> PyResult<Option<T>>
+where
+    T: for<'a> FromPyObject<'a, 'py, Error = PyErr>,
+{
+    match dict.get_item(key)? {
+        Some(value) if !value.is_none() => Ok(Some(value.extract()?)),
+        _ => Ok(None),
+    }
+}
+
+/// Get the value of key from dict, a missing key or `None` value is an error.
+pub(crate) fn extract_dict_item_required<'py, T>(
+    dict: &Bound<'py, PyDict>,
+    key: &str,
+) -> PyResult<T>
+where
+    T: for<'a> FromPyObject<'a, 'py, Error = PyErr>,
+{
+    extract_dict_item(dict, key)?.ok_or_else(|| {
+        PyErr::new::<PyValueError, _>(format!("Missing required key `{}` of dict", key))
     })
 }
 
+/// Convert the arg `timeout` in seconds to Duration, it must be non-negative.
+pub(crate) fn parse_timeout(timeout: f64) -> PyResult<std::time::Duration> {
+    std::time::Duration::try_from_secs_f64(timeout).map_err(|_| {
+        PyErr::new::<PyValueError, _>("Arg `timeout` must be a non-negative number of seconds")
+    })
+}
+
+/// Init the process-wide runtime with worker_threads, default the count of CPU cores.
+/// It must be called before building any client, otherwise the runtime is initialized already.
+/// In the child process of fork, it could be called again before building any client there.
+#[pyfunction]
+#[pyo3(signature = (worker_threads=None))]
+fn init_runtime(worker_threads: Option<usize>) -> PyResult<()> {
+    if worker_threads == Some(0) {
+        return Err(PyErr::new::<PyValueError, _>(
+            "Arg `worker_threads` must be positive",
+        ));
+    }
+    let runtime = build_runtime(worker_threads)
+        .map_err(|io_err| PyRuntimeError::new_err(format!("{:?}", &io_err)))?;
+    if let Err(runtime) = set_runtime(runtime) {
+        runtime.shutdown_background();
+        return Err(PyRuntimeError::new_err(
+            "The runtime is initialized already, call init_runtime before building any client",
+        ));
+    }
+    Ok(())
+}
+
 /// Formats the sum of two numbers as string.
 #[pyfunction]
 fn sum_as_string(a: usize, b: usize) -> PyResult<String> {
@@ -33,11 +203,29 @@ fn sum_as_string(a: usize, b: usize) -> PyResult<String> {
 #[pymodule]
 fn nacos_sdk_rust_binding_py(m: Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(sum_as_string, &m)?)?;
+    m.add_function(wrap_pyfunction!(init_runtime, &m)?)?;
+    m.add_function(wrap_pyfunction!(metrics::metrics, &m)?)?;
+    m.add_function(wrap_pyfunction!(metrics::metrics_prometheus, &m)?)?;
+    m.add_function(wrap_pyfunction!(logging::enable_log_forwarding, &m)?)?;
+    m.add_function(wrap_pyfunction!(logging::set_log_level, &m)?)?;
+    m.add_function(wrap_pyfunction!(otel::enable_opentelemetry, &m)?)?;
+    // TLS of the connections with server, by the cargo feature `tls` of nacos-sdk
+    m.add("TLS_ENABLED", cfg!(feature = "tls"))?;
+    fork::register_at_fork(&m)?;
     m.add_class::<ClientOptions>()?;
     m.add_class::<NacosConfigClient>()?;
     m.add_class::<NacosConfigResponse>()?;
     m.add_class::<NacosNamingClient>()?;
     m.add_class::<NacosServiceInstance>()?;
+    m.add_class::<NacosServiceInstanceList>()?;
+    // isinstance(instances, collections.abc.Sequence) is true
+    m.py()
+        .import("collections.abc")?
+        .getattr("Sequence")?
+        .call_method1("register", (m.getattr("NacosServiceInstanceList")?,))?;
+    m.add_class::<LoadBalancer>()?;
+    m.add_class::<RegistrationManager>()?;
+    m.add_class::<NacosHealthCheckResult>()?;
     // Async Client api
     m.add_class::<AsyncNacosConfigClient>()?;
     m.add_class::<AsyncNacosNamingClient>()?;
@@ -45,7 +233,7 @@ fn nacos_sdk_rust_binding_py(m: Bound<'_, PyModule>) -> PyResult<()> {
 }
 
 #[pyclass(module = "nacos_sdk_rust_binding_py", from_py_object)]
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct ClientOptions {
     /// Server Addr, e.g. address:port[,address:port],...]
     #[pyo3(set, get)]
@@ -88,12 +276,45 @@ pub struct ClientOptions {
     /// Priority: endpoint > server_addr (endpoint takes precedence if both are set)
     #[pyo3(set, get)]
     pub endpoint: Option<String>,
+    /// Remote gRPC port, default is the port of server_addr + 1000
+    #[pyo3(set, get)]
+    pub grpc_port: Option<u16>,
+    /// Local cache dir of config and naming, default `$HOME/nacos`
+    #[pyo3(set, get)]
+    pub cache_dir: Option<String>,
+    /// Consecutive failures reported to eject an instance from select results, default 5
+    #[pyo3(set, get)]
+    pub outlier_failure_threshold: Option<u32>,
+    /// Ejection time of an instance in milliseconds, default 30000
+    #[pyo3(set, get)]
+    pub outlier_ejection_time_ms: Option<u64>,
+    /// Max percent of the instances of service could be ejected, rounded up, default 50
+    #[pyo3(set, get)]
+    pub outlier_max_ejection_percent: Option<u32>,
+    /// Interval in milliseconds of reconciling the registered instances with server, default None is disabled, by `RegistrationManager.reconcile` only
+    #[pyo3(set, get)]
+    pub registration_reconcile_interval_ms: Option<u64>,
+    /// Zone of this client, select_one_healthy_instance prefers the instances of which metadata `zone` matches
+    #[pyo3(set, get)]
+    pub locality_zone: Option<String>,
+    /// Cluster of this client, select_one_healthy_instance prefers the instances of which cluster_name matches
+    #[pyo3(set, get)]
+    pub locality_cluster: Option<String>,
+    /// Min percent of the healthy local instances, below it select_one_healthy_instance falls back to all the zones, default 50
+    #[pyo3(set, get)]
+    pub locality_min_healthy_percent: Option<u32>,
+    /// Failover dir of config, the content in `{dir}/{namespace}/{group}/{data_id}` is served when the server is unavailable, and it is updated on every successful fetch
+    #[pyo3(set, get)]
+    pub config_failover_dir: Option<String>,
+    /// Context path of the http open api of server, used by health_check, metadata and export, default `/nacos`
+    #[pyo3(set, get)]
+    pub context_path: Option<String>,
 }
 
 #[pymethods]
 impl ClientOptions {
     #[new]
-    #[pyo3(signature = (server_addr, namespace, app_name=None, username=None, password=None, access_key=None, access_secret=None, signature_region_id=None, naming_push_empty_protection=None, naming_load_cache_at_start=None, config_load_cache_at_start=None, endpoint=None))]
+    #[pyo3(signature = (server_addr, namespace, app_name=None, username=None, password=None, access_key=None, access_secret=None, signature_region_id=None, naming_push_empty_protection=None, naming_load_cache_at_start=None, config_load_cache_at_start=None, endpoint=None, grpc_port=None, cache_dir=None, outlier_failure_threshold=None, outlier_ejection_time_ms=None, outlier_max_ejection_percent=None, registration_reconcile_interval_ms=None, locality_zone=None, locality_cluster=None, locality_min_healthy_percent=None, config_failover_dir=None, context_path=None))]
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         server_addr: String,
@@ -108,6 +329,17 @@ impl ClientOptions {
         naming_load_cache_at_start: Option<bool>,
         config_load_cache_at_start: Option<bool>,
         endpoint: Option<String>,
+        grpc_port: Option<u16>,
+        cache_dir: Option<String>,
+        outlier_failure_threshold: Option<u32>,
+        outlier_ejection_time_ms: Option<u64>,
+        outlier_max_ejection_percent: Option<u32>,
+        registration_reconcile_interval_ms: Option<u64>,
+        locality_zone: Option<String>,
+        locality_cluster: Option<String>,
+        locality_min_healthy_percent: Option<u32>,
+        config_failover_dir: Option<String>,
+        context_path: Option<String>,
     ) -> PyResult<ClientOptions> {
         Ok(Self {
             server_addr,
@@ -122,16 +354,228 @@ impl ClientOptions {
             naming_load_cache_at_start,
             config_load_cache_at_start,
             endpoint,
+            grpc_port,
+            cache_dir,
+            outlier_failure_threshold,
+            outlier_ejection_time_ms,
+            outlier_max_ejection_percent,
+            registration_reconcile_interval_ms,
+            locality_zone,
+            locality_cluster,
+            locality_min_healthy_percent,
+            config_failover_dir,
+            context_path,
         })
     }
+
+    pub fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let fields = PyDict::new(py);
+        for (key, value) in self.fields(py)? {
+            // secrets are redacted
+            if (key == "password" || key == "access_secret") && !value.is_none() {
+                fields.set_item(key, "******")?;
+            } else {
+                fields.set_item(key, value)?;
+            }
+        }
+        repr_fields("ClientOptions", &fields)
+    }
+
+    /// Equal by all fields. It is mutable, so it is not hashable.
+    pub fn __eq__(&self, other: PyRef<'_, Self>) -> bool {
+        *self == *other
+    }
+
+    pub fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    /// Support pickle, e.g. send to multiprocessing workers.
+    pub fn __reduce__<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<(Bound<'py, PyType>, Bound<'py, PyTuple>)> {
+        let args = self.fields(py)?.into_iter().map(|(_, value)| value);
+        Ok((py.get_type::<Self>(), PyTuple::new(py, args)?))
+    }
+}
+
+/// Auth plugin of nacos-sdk enabled by the ClientOptions.
+pub(crate) enum AuthPlugin {
+    None,
+    /// Login by Http with username & password
+    Http,
+    /// Login by Aliyun Ram with access_key & access_secret
+    Aliyun,
+}
+
+impl ClientOptions {
+    /// Props of nacos-sdk shared by the config and naming clients, and the auth plugin to enable.
+    /// If it fails, pay attention to err
+    pub(crate) fn build_client_props(
+        &self,
+    ) -> PyResult<(nacos_sdk::api::props::ClientProps, AuthPlugin)> {
+        let props = nacos_sdk::api::props::ClientProps::new()
+            .server_addr(self.server_addr.clone())
+            .namespace(self.namespace.clone())
+            .app_name(
+                self.app_name
+                    .clone()
+                    .unwrap_or(nacos_sdk::api::constants::UNKNOWN.to_string()),
+            )
+            .naming_push_empty_protection(self.naming_push_empty_protection.unwrap_or(true))
+            .naming_load_cache_at_start(self.naming_load_cache_at_start.unwrap_or(false))
+            .config_load_cache_at_start(self.config_load_cache_at_start.unwrap_or(false));
+
+        let props = if let Some(ep) = self.endpoint.clone() {
+            props.endpoint(ep)
+        } else {
+            props
+        };
+
+        let props = if let Some(grpc_port) = self.grpc_port {
+            props.remote_grpc_port(grpc_port)
+        } else {
+            props
+        };
+
+        let props = if let Some(cache_dir) = self.cache_dir.clone() {
+            props.cache_dir(cache_dir)
+        } else {
+            props
+        };
+
+        // need enable_auth_plugin_http with username & password
+        if let (Some(username), Some(password)) = (self.username.clone(), self.password.clone()) {
+            let props = props.auth_username(username).auth_password(password);
+            return Ok((props, AuthPlugin::Http));
+        }
+        // need enable_auth_plugin_aliyun with access_key & access_secret
+        if let (Some(access_key), Some(access_secret)) =
+            (self.access_key.clone(), self.access_secret.clone())
+        {
+            let props = props
+                .auth_access_key(access_key)
+                .auth_access_secret(access_secret)
+                .auth_signature_region_id(self.signature_region_id.clone().unwrap_or_default());
+            return Ok((props, AuthPlugin::Aliyun));
+        }
+        Ok((props, AuthPlugin::None))
+    }
+
+    /// All fields, in the order of the args of `ClientOptions.__init__`.
+    fn fields<'py>(&self, py: Python<'py>) -> PyResult<Vec<(&'static str, Bound<'py, PyAny>)>> {
+        Ok(vec![
+            (
+                "server_addr",
+                self.server_addr.clone().into_bound_py_any(py)?,
+            ),
+            ("namespace", self.namespace.clone().into_bound_py_any(py)?),
+            ("app_name", self.app_name.clone().into_bound_py_any(py)?),
+            ("username", self.username.clone().into_bound_py_any(py)?),
+            ("password", self.password.clone().into_bound_py_any(py)?),
+            ("access_key", self.access_key.clone().into_bound_py_any(py)?),
+            (
+                "access_secret",
+                self.access_secret.clone().into_bound_py_any(py)?,
+            ),
+            (
+                "signature_region_id",
+                self.signature_region_id.clone().into_bound_py_any(py)?,
+            ),
+            (
+                "naming_push_empty_protection",
+                self.naming_push_empty_protection.into_bound_py_any(py)?,
+            ),
+            (
+                "naming_load_cache_at_start",
+                self.naming_load_cache_at_start.into_bound_py_any(py)?,
+            ),
+            (
+                "config_load_cache_at_start",
+                self.config_load_cache_at_start.into_bound_py_any(py)?,
+            ),
+            ("endpoint", self.endpoint.clone().into_bound_py_any(py)?),
+            ("grpc_port", self.grpc_port.into_bound_py_any(py)?),
+            ("cache_dir", self.cache_dir.clone().into_bound_py_any(py)?),
+            (
+                "outlier_failure_threshold",
+                self.outlier_failure_threshold.into_bound_py_any(py)?,
+            ),
+            (
+                "outlier_ejection_time_ms",
+                self.outlier_ejection_time_ms.into_bound_py_any(py)?,
+            ),
+            (
+                "outlier_max_ejection_percent",
+                self.outlier_max_ejection_percent.into_bound_py_any(py)?,
+            ),
+            (
+                "registration_reconcile_interval_ms",
+                self.registration_reconcile_interval_ms
+                    .into_bound_py_any(py)?,
+            ),
+            (
+                "locality_zone",
+                self.locality_zone.clone().into_bound_py_any(py)?,
+            ),
+            (
+                "locality_cluster",
+                self.locality_cluster.clone().into_bound_py_any(py)?,
+            ),
+            (
+                "locality_min_healthy_percent",
+                self.locality_min_healthy_percent.into_bound_py_any(py)?,
+            ),
+            (
+                "config_failover_dir",
+                self.config_failover_dir.clone().into_bound_py_any(py)?,
+            ),
+            (
+                "context_path",
+                self.context_path.clone().into_bound_py_any(py)?,
+            ),
+        ])
+    }
 }
 
 mod config;
 pub use config::*;
 
+mod config_transfer;
+
+mod failover;
+
+mod connection;
+pub use connection::NacosHealthCheckResult;
+
+mod open_api;
+
+mod otel;
+
+mod metrics;
+
+mod logging;
+
+mod fork;
+
 mod naming;
 pub use naming::*;
 
+mod load_balancer;
+pub use load_balancer::LoadBalancer;
+
+mod outlier;
+
+mod health_check;
+
+mod registration;
+pub use registration::RegistrationManager;
+
+mod selector;
+
+mod locality;
+
 mod async_config;
 pub use async_config::*;
 
@@ -0,0 +1,423 @@
+#![deny(clippy::all)]
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::{PyErr, PyResult, pyclass, pymethods};
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use nacos_sdk::api::naming::{NamingService, ServiceInstance};
+
+use crate::naming::{
+    NacosServiceInstance, NacosServiceInstanceList, transfer_rust_instance_to_ffi,
+};
+use crate::outlier::{OutlierDetector, instance_key};
+
+/// Virtual nodes of per instance on the consistent-hash ring.
+const VIRTUAL_NODES: usize = 160;
+
+/// Strategy of LoadBalancer to pick an instance.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Strategy {
+    WeightedRandom,
+    RoundRobin,
+    LeastRecentlyUsed,
+    ConsistentHash,
+}
+
+impl Strategy {
+    pub(crate) fn parse(strategy: Option<String>) -> PyResult<Self> {
+        match strategy.as_deref() {
+            None | Some("weighted_random") => Ok(Strategy::WeightedRandom),
+            Some("round_robin") => Ok(Strategy::RoundRobin),
+            Some("least_recently_used") => Ok(Strategy::LeastRecentlyUsed),
+            Some("consistent_hash") => Ok(Strategy::ConsistentHash),
+            Some(other) => Err(PyErr::new::<PyValueError, _>(format!(
+                "Unknown strategy `{}`, must be one of weighted_random, round_robin, least_recently_used, consistent_hash",
+                other
+            ))),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Strategy::WeightedRandom => "weighted_random",
+            Strategy::RoundRobin => "round_robin",
+            Strategy::LeastRecentlyUsed => "least_recently_used",
+            Strategy::ConsistentHash => "consistent_hash",
+        }
+    }
+}
+
+/// Local snapshot of the instances of a service, updated by the naming subscribe push.
+pub(crate) type InstanceSnapshot = Arc<RwLock<Arc<Vec<ServiceInstance>>>>;
+
+struct InstanceSnapshotListener {
+    snapshot: InstanceSnapshot,
+}
+
+impl nacos_sdk::api::naming::NamingEventListener for InstanceSnapshotListener {
+    fn event(&self, event: Arc<nacos_sdk::api::naming::NamingChangeEvent>) {
+        if let Some(instances) = &event.instances {
+            *self.snapshot.write().unwrap() = Arc::new(instances.clone());
+        }
+    }
+}
+
+/// (service_name, group, clusters) of a snapshot, the clusters are sorted.
+type SnapshotKey = (String, String, Vec<String>);
+
+struct SubscribedSnapshot {
+    snapshot: InstanceSnapshot,
+    listener: Arc<InstanceSnapshotListener>,
+    /// Count of the LoadBalancers not closed
+    ref_count: usize,
+}
+
+/// Snapshots subscribed by the LoadBalancers of a naming client, one per (service_name, group, clusters)
+/// shared by its LoadBalancers, and unsubscribed when the last of them is closed.
+pub(crate) struct InstanceSnapshots {
+    naming_service: NamingService,
+    subscribed: Mutex<HashMap<SnapshotKey, SubscribedSnapshot>>,
+}
+
+impl InstanceSnapshots {
+    pub(crate) fn new(naming_service: NamingService) -> Self {
+        Self {
+            naming_service,
+            subscribed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn acquire_subscribed(&self, key: &SnapshotKey) -> Option<InstanceSnapshot> {
+        let mut subscribed = self.subscribed.lock().unwrap();
+        let subscribed = subscribed.get_mut(key)?;
+        subscribed.ref_count += 1;
+        Some(subscribed.snapshot.clone())
+    }
+
+    /// Get the snapshot of service, which is loaded then kept updated through subscribe at first.
+    pub(crate) async fn acquire(
+        self: &Arc<Self>,
+        service_name: String,
+        group: String,
+        mut clusters: Vec<String>,
+    ) -> nacos_sdk::api::error::Result<InstanceSnapshot> {
+        clusters.sort();
+        let key = (service_name, group, clusters);
+        if let Some(snapshot) = self.acquire_subscribed(&key) {
+            return Ok(snapshot);
+        }
+
+        let (service_name, group, clusters) = key.clone();
+        let instances = self
+            .naming_service
+            .get_all_instances(
+                service_name.clone(),
+                Some(group.clone()),
+                clusters.clone(),
+                true,
+            )
+            .await?;
+        let snapshot: InstanceSnapshot = Arc::new(RwLock::new(Arc::new(instances)));
+        let listener = Arc::new(InstanceSnapshotListener {
+            snapshot: snapshot.clone(),
+        });
+        self.naming_service
+            .subscribe(service_name, Some(group), clusters, listener.clone())
+            .await?;
+
+        // another LoadBalancer of the same service may subscribe in between, share its snapshot
+        if let Some(snapshot) = self.acquire_subscribed(&key) {
+            self.unsubscribe(key, listener);
+            return Ok(snapshot);
+        }
+        self.subscribed.lock().unwrap().insert(
+            key,
+            SubscribedSnapshot {
+                snapshot: snapshot.clone(),
+                listener,
+                ref_count: 1,
+            },
+        );
+        crate::metrics::add_subscription("naming");
+        Ok(snapshot)
+    }
+
+    /// A LoadBalancer of the snapshot is closed, unsubscribe it if it is the last one.
+    fn release(&self, key: &SnapshotKey) {
+        let listener = {
+            let mut subscribed = self.subscribed.lock().unwrap();
+            let Some(snapshot) = subscribed.get_mut(key) else {
+                return;
+            };
+            snapshot.ref_count -= 1;
+            if snapshot.ref_count > 0 {
+                return;
+            }
+            subscribed.remove(key).unwrap().listener
+        };
+        crate::metrics::remove_subscription("naming");
+        self.unsubscribe(key.clone(), listener);
+    }
+
+    /// Unsubscribe on the runtime, never wait for it, since it may be called when dropped.
+    fn unsubscribe(&self, key: SnapshotKey, listener: Arc<InstanceSnapshotListener>) {
+        let naming_service = self.naming_service.clone();
+        let (service_name, group, clusters) = key;
+        crate::runtime().spawn(async move {
+            let _ = naming_service
+                .unsubscribe(service_name, Some(group), clusters, listener)
+                .await;
+        });
+    }
+}
+
+/// Load balancer over the local instance snapshot of a service, it picks without network round trips.
+/// Build by `NacosNamingClient.load_balancer()` or `AsyncNacosNamingClient.load_balancer()`.
+#[pyclass(module = "nacos_sdk_rust_binding_py")]
+pub struct LoadBalancer {
+    /// Service Name
+    #[pyo3(get)]
+    service_name: String,
+    /// Group
+    #[pyo3(get)]
+    group: String,
+    /// Clusters
+    #[pyo3(get)]
+    clusters: Vec<String>,
+    strategy: Strategy,
+    snapshot: InstanceSnapshot,
+    snapshots: Arc<InstanceSnapshots>,
+    closed: AtomicBool,
+    /// for round_robin
+    next_index: AtomicUsize,
+    /// for least_recently_used, ip:port -> the sequence of last picked
+    last_picked: Mutex<HashMap<String, u64>>,
+    pick_sequence: AtomicU64,
+    /// for consistent_hash, the ring built from the snapshot
+    hash_ring: Mutex<Option<HashRing>>,
+    outlier_detector: Arc<OutlierDetector>,
+}
+
+struct HashRing {
+    instances: Arc<Vec<ServiceInstance>>,
+    /// (hash, index of instances), sorted by hash
+    nodes: Vec<(u64, usize)>,
+}
+
+impl LoadBalancer {
+    pub(crate) fn new(
+        service_name: String,
+        group: String,
+        clusters: Vec<String>,
+        strategy: Strategy,
+        snapshot: InstanceSnapshot,
+        snapshots: Arc<InstanceSnapshots>,
+        outlier_detector: Arc<OutlierDetector>,
+    ) -> Self {
+        Self {
+            service_name,
+            group,
+            clusters,
+            strategy,
+            snapshot,
+            snapshots,
+            closed: AtomicBool::new(false),
+            next_index: AtomicUsize::new(0),
+            last_picked: Mutex::new(HashMap::new()),
+            pick_sequence: AtomicU64::new(0),
+            hash_ring: Mutex::new(None),
+            outlier_detector,
+        }
+    }
+
+    fn check_closed(&self) -> PyResult<()> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(PyErr::new::<PyRuntimeError, _>(format!(
+                "LoadBalancer of service `{}` in group `{}` is closed",
+                self.service_name, self.group
+            )));
+        }
+        Ok(())
+    }
+
+    fn pick_round_robin<'a>(&self, candidates: &[&'a ServiceInstance]) -> &'a ServiceInstance {
+        let index = self.next_index.fetch_add(1, Ordering::Relaxed);
+        candidates[index % candidates.len()]
+    }
+
+    fn pick_least_recently_used<'a>(
+        &self,
+        candidates: &[&'a ServiceInstance],
+    ) -> &'a ServiceInstance {
+        let mut last_picked = self.last_picked.lock().unwrap();
+        let picked = candidates
+            .iter()
+            .min_by_key(|instance| last_picked.get(&instance.ip_and_port()).copied())
+            .unwrap();
+
+        let sequence = self.pick_sequence.fetch_add(1, Ordering::Relaxed) + 1;
+        // forget the instances which are gone
+        if last_picked.len() > candidates.len() {
+            let alive: Vec<String> = candidates.iter().map(|i| i.ip_and_port()).collect();
+            last_picked.retain(|key, _| alive.contains(key));
+        }
+        last_picked.insert(picked.ip_and_port(), sequence);
+        picked
+    }
+
+    fn pick_consistent_hash(
+        &self,
+        instances: &Arc<Vec<ServiceInstance>>,
+        ejected: &HashSet<String>,
+        key: &str,
+    ) -> Option<ServiceInstance> {
+        let mut hash_ring = self.hash_ring.lock().unwrap();
+        let rebuild = match hash_ring.as_ref() {
+            Some(ring) => !Arc::ptr_eq(&ring.instances, instances),
+            None => true,
+        };
+        if rebuild {
+            let mut nodes = Vec::new();
+            for (index, instance) in instances.iter().enumerate() {
+                if !is_candidate(instance) {
+                    continue;
+                }
+                let ip_and_port = instance.ip_and_port();
+                for node in 0..VIRTUAL_NODES {
+                    nodes.push((fnv1a_hash(&format!("{}#{}", ip_and_port, node)), index));
+                }
+            }
+            nodes.sort_unstable();
+            *hash_ring = Some(HashRing {
+                instances: instances.clone(),
+                nodes,
+            });
+        }
+
+        let ring = hash_ring.as_ref().unwrap();
+        if ring.nodes.is_empty() {
+            return None;
+        }
+        let hash = fnv1a_hash(key);
+        let position = ring
+            .nodes
+            .partition_point(|(node_hash, _)| *node_hash < hash);
+        // walk clockwise, skip the ejected instances
+        (0..ring.nodes.len())
+            .map(|offset| &ring.instances[ring.nodes[(position + offset) % ring.nodes.len()].1])
+            .find(|instance| !ejected.contains(&instance_key(&instance.ip, instance.port)))
+            .cloned()
+    }
+}
+
+#[pymethods]
+impl LoadBalancer {
+    /// Strategy, one of weighted_random, round_robin, least_recently_used, consistent_hash
+    #[getter]
+    pub fn strategy(&self) -> &'static str {
+        self.strategy.as_str()
+    }
+
+    /// Pick one healthy instance from the local snapshot, `key` is required by consistent_hash.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (key=None))]
+    pub fn select(&self, key: Option<String>) -> PyResult<NacosServiceInstance> {
+        self.check_closed()?;
+        let instances = self.snapshot.read().unwrap().clone();
+        let ejected = self
+            .outlier_detector
+            .ejected_among(instances.iter().filter(|i| is_candidate(i)));
+
+        let picked = match self.strategy {
+            Strategy::ConsistentHash => {
+                let key = key.ok_or_else(|| {
+                    PyErr::new::<PyValueError, _>("Arg `key` is required by consistent_hash")
+                })?;
+                self.pick_consistent_hash(&instances, &ejected, &key)
+            }
+            _ => {
+                let candidates: Vec<&ServiceInstance> = instances
+                    .iter()
+                    .filter(|i| is_candidate(i) && !ejected.contains(&instance_key(&i.ip, i.port)))
+                    .collect();
+                if candidates.is_empty() {
+                    None
+                } else {
+                    let picked = match self.strategy {
+                        Strategy::RoundRobin => self.pick_round_robin(&candidates),
+                        Strategy::LeastRecentlyUsed => self.pick_least_recently_used(&candidates),
+                        _ => pick_weighted_random(&candidates),
+                    };
+                    Some(picked.clone())
+                }
+            }
+        };
+
+        picked
+            .map(|instance| transfer_rust_instance_to_ffi(&instance))
+            .ok_or_else(|| {
+                PyErr::new::<PyRuntimeError, _>(format!(
+                    "No healthy instance of service `{}` in group `{}`",
+                    self.service_name, self.group
+                ))
+            })
+    }
+
+    /// All instances of the local snapshot, whether healthy or not.
+    /// If it fails, pay attention to err
+    pub fn instances(&self) -> PyResult<NacosServiceInstanceList> {
+        self.check_closed()?;
+        Ok(NacosServiceInstanceList::from_rust(
+            self.snapshot.read().unwrap().clone(),
+        ))
+    }
+
+    /// Close it, the subscription of its snapshot is removed when the last LoadBalancer of the same
+    /// service, group and clusters is closed. It is closed when garbage collected too.
+    pub fn close(&self) {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let mut clusters = self.clusters.clone();
+        clusters.sort();
+        self.snapshots
+            .release(&(self.service_name.clone(), self.group.clone(), clusters));
+    }
+}
+
+impl Drop for LoadBalancer {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Pick one by weighted random, the candidates must not be empty.
+pub(crate) fn pick_weighted_random<'a>(candidates: &[&'a ServiceInstance]) -> &'a ServiceInstance {
+    let total_weight: f64 = candidates.iter().map(|instance| instance.weight).sum();
+    let mut random = rand::random::<f64>() * total_weight;
+    for instance in candidates {
+        random -= instance.weight;
+        if random < 0.0 {
+            return instance;
+        }
+    }
+    candidates[candidates.len() - 1]
+}
+
+/// Instance could be picked, which is healthy, enabled and weight > 0.
+pub(crate) fn is_candidate(instance: &ServiceInstance) -> bool {
+    instance.healthy && instance.enabled && instance.weight > 0.0
+}
+
+/// FNV-1a, a stable hash across processes for consistent_hash.
+fn fnv1a_hash(key: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in key.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
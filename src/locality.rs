@@ -0,0 +1,86 @@
+#![deny(clippy::all)]
+
+use nacos_sdk::api::naming::ServiceInstance;
+
+use crate::load_balancer::is_candidate;
+use crate::outlier::{OutlierDetector, instance_key};
+
+/// The metadata key of the zone of instance.
+const ZONE_METADATA_KEY: &str = "zone";
+/// Min percent of the healthy local instances, default 50
+const DEFAULT_MIN_HEALTHY_PERCENT: u32 = 50;
+
+/// Zone or cluster of the caller, the instances in the same zone or cluster are preferred.
+#[derive(Clone, Debug)]
+pub(crate) struct Locality {
+    zone: Option<String>,
+    cluster: Option<String>,
+    min_healthy_percent: u32,
+}
+
+impl Locality {
+    pub(crate) fn new(client_options: &crate::ClientOptions) -> Self {
+        Self {
+            zone: client_options.locality_zone.clone(),
+            cluster: client_options.locality_cluster.clone(),
+            min_healthy_percent: client_options
+                .locality_min_healthy_percent
+                .unwrap_or(DEFAULT_MIN_HEALTHY_PERCENT)
+                .min(100),
+        }
+    }
+
+    /// Override the zone or cluster by the args of per call.
+    pub(crate) fn with(&self, zone: Option<String>, cluster: Option<String>) -> Self {
+        Self {
+            zone: zone.or_else(|| self.zone.clone()),
+            cluster: cluster.or_else(|| self.cluster.clone()),
+            min_healthy_percent: self.min_healthy_percent,
+        }
+    }
+
+    fn is_local(&self, instance: &ServiceInstance) -> bool {
+        let same_cluster = self
+            .cluster
+            .as_ref()
+            .is_some_and(|cluster| instance.cluster_name.as_ref() == Some(cluster));
+        let same_zone = self
+            .zone
+            .as_ref()
+            .is_some_and(|zone| instance.metadata.get(ZONE_METADATA_KEY) == Some(zone));
+        same_cluster || same_zone
+    }
+
+    /// Keep the local instances only, unless the percent of the healthy and not ejected ones
+    /// among them drops below min_healthy_percent, then the instances of all the zones are kept.
+    pub(crate) fn prefer_local(
+        &self,
+        instances: Vec<ServiceInstance>,
+        outlier_detector: &OutlierDetector,
+    ) -> Vec<ServiceInstance> {
+        if self.zone.is_none() && self.cluster.is_none() {
+            return instances;
+        }
+        let local_total = instances.iter().filter(|i| self.is_local(i)).count();
+        if local_total == 0 {
+            return instances;
+        }
+
+        let ejected = outlier_detector.ejected_among(instances.iter().filter(|i| is_candidate(i)));
+        let local_healthy = instances
+            .iter()
+            .filter(|i| {
+                self.is_local(i)
+                    && is_candidate(i)
+                    && !ejected.contains(&instance_key(&i.ip, i.port))
+            })
+            .count();
+        if local_healthy == 0
+            || local_healthy * 100 < local_total * self.min_healthy_percent as usize
+        {
+            return instances;
+        }
+
+        instances.into_iter().filter(|i| self.is_local(i)).collect()
+    }
+}
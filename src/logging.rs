@@ -0,0 +1,276 @@
+#![deny(clippy::all)]
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+use std::fmt::{Debug, Write};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicU64, Ordering};
+use std::sync::mpsc::{SyncSender, TrySendError, sync_channel};
+use std::thread::JoinHandle;
+
+use tracing::field::{Field, Visit};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber, span};
+
+/// Target of the events of nacos-sdk, e.g. `nacos_sdk::config::worker`.
+const NACOS_SDK_TARGET: &str = "nacos_sdk";
+/// Target of the event of nacos-sdk failing to install its subscriber, which is expected once forwarding.
+const NACOS_SDK_LOG_TARGET: &str = "nacos_sdk::common::log";
+/// Capacity of the records waiting for the forwarder, the others are dropped when it is full.
+const FORWARD_CAPACITY: usize = 4096;
+
+const LEVEL_OFF: u8 = 0;
+/// The max level forwarded, ERROR(1) < WARN(2) < INFO(3) < DEBUG(4) < TRACE(5), OFF(0) forwards nothing
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(3);
+/// The thread calling python logging, replaced in the child process after fork, null when stopped at exit.
+/// It is leaked and never locked by the events, so that the child process after fork never waits for
+/// a lock held by the threads not existing there.
+static FORWARDER: AtomicPtr<Forwarder> = AtomicPtr::new(std::ptr::null_mut());
+/// Whether the subscriber forwarding to python logging is installed, by `enable_log_forwarding`
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Count of the events dropped since python logging falls behind
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+struct Forwarder {
+    /// None stops the thread after the records before it are forwarded
+    sender: SyncSender<Option<LogRecord>>,
+    /// Only taken by `stop_forwarder` in the process it is started in
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+struct LogRecord {
+    logger: String,
+    level: u8,
+    message: String,
+}
+
+fn level_of(level: &Level) -> u8 {
+    match *level {
+        Level::ERROR => 1,
+        Level::WARN => 2,
+        Level::INFO => 3,
+        Level::DEBUG => 4,
+        _ => 5,
+    }
+}
+
+/// Level of python logging, e.g. logging.WARNING is 30.
+fn python_level_of(level: u8) -> u8 {
+    match level {
+        1 => 40,
+        2 => 30,
+        3 => 20,
+        4 => 10,
+        _ => 5,
+    }
+}
+
+fn parse_level(level: &str) -> PyResult<u8> {
+    match level.to_ascii_uppercase().as_str() {
+        "OFF" => Ok(LEVEL_OFF),
+        "ERROR" => Ok(1),
+        "WARN" | "WARNING" => Ok(2),
+        "INFO" => Ok(3),
+        "DEBUG" => Ok(4),
+        "TRACE" => Ok(5),
+        _ => Err(PyErr::new::<PyValueError, _>(format!(
+            "Unknown log level `{}`, must be one of TRACE, DEBUG, INFO, WARN, ERROR, OFF",
+            level
+        ))),
+    }
+}
+
+fn is_nacos_sdk(target: &str) -> bool {
+    target == NACOS_SDK_TARGET || target.starts_with("nacos_sdk::")
+}
+
+/// Logger name of the target, the module of nacos-sdk, e.g. `nacos_sdk::config::worker` is `nacos_sdk.config`.
+fn logger_of(target: &str) -> String {
+    target.split("::").take(2).collect::<Vec<_>>().join(".")
+}
+
+/// Format the fields of event, the message then the others as `key=value`.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else if !field.name().starts_with("log.") {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// The subscriber of tracing, which forwards the events of nacos-sdk to python logging.
+/// The events are sent to a dedicated thread calling python, so that the runtime threads never
+/// wait for the GIL, which may be held by the python thread waiting for them.
+struct PythonLogging;
+
+impl Subscriber for PythonLogging {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        // sometimes, since the level could be changed at runtime
+        if is_nacos_sdk(metadata.target()) {
+            Interest::sometimes()
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        is_nacos_sdk(metadata.target())
+            && level_of(metadata.level()) <= MAX_LEVEL.load(Ordering::Relaxed)
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+        // the spans are not forwarded, only the events
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        if metadata.target() == NACOS_SDK_LOG_TARGET {
+            // nacos-sdk fails to install its subscriber of log files, since this one is installed
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        visitor.message.push_str(&visitor.fields);
+
+        let record = LogRecord {
+            logger: logger_of(metadata.target()),
+            level: python_level_of(level_of(metadata.level())),
+            message: visitor.message,
+        };
+        let forwarder = FORWARDER.load(Ordering::Acquire);
+        if forwarder.is_null() {
+            return;
+        }
+        // SAFETY: the forwarders are leaked, so they live as long as the process
+        let forwarder = unsafe { &*forwarder };
+        // never block the runtime threads, drop it when python logging falls behind
+        if let Err(TrySendError::Full(_)) = forwarder.sender.try_send(Some(record)) {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+/// Start the thread calling python logging, it ends when stopped at exit.
+fn start_forwarder() {
+    let (sender, receiver) = sync_channel::<Option<LogRecord>>(FORWARD_CAPACITY);
+    let spawned = std::thread::Builder::new()
+        .name("nacos-logging-forwarder".to_string())
+        .spawn(move || {
+            while let Ok(Some(record)) = receiver.recv() {
+                let attached = Python::try_attach(|py| {
+                    let _ = py.import("logging").and_then(|logging| {
+                        logging
+                            .call_method1("getLogger", (record.logger,))?
+                            .call_method1("log", (record.level, "%s", record.message))
+                    });
+                });
+                if attached.is_none() {
+                    break;
+                }
+            }
+        });
+    if let Ok(thread) = spawned {
+        let forwarder = Box::into_raw(Box::new(Forwarder {
+            sender,
+            thread: Mutex::new(Some(thread)),
+        }));
+        // the previous one is leaked, its thread does not exist in the child process after fork
+        FORWARDER.store(forwarder, Ordering::Release);
+    }
+}
+
+/// The forwarder thread does not exist in the child process after fork, start another one.
+pub(crate) fn after_fork_in_child() {
+    if !FORWARDER.load(Ordering::Acquire).is_null() {
+        start_forwarder();
+    }
+}
+
+/// Count of the events of nacos-sdk dropped, since python logging falls behind.
+pub(crate) fn dropped_events() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Called by `atexit`, stop the forwarder before python is finalized, since the thread attached
+/// to python while it is finalizing aborts the process. The events after it are dropped.
+#[pyfunction]
+fn stop_forwarder(py: Python<'_>) {
+    let forwarder = FORWARDER.swap(std::ptr::null_mut(), Ordering::AcqRel);
+    if forwarder.is_null() {
+        return;
+    }
+    // SAFETY: the forwarders are leaked, so they live as long as the process
+    let forwarder = unsafe { &*forwarder };
+    let Some(thread) = forwarder.thread.lock().unwrap().take() else {
+        return;
+    };
+    py.detach(move || {
+        // the thread forwards the records left then ends
+        if forwarder.sender.send(None).is_ok() {
+            let _ = thread.join();
+        }
+    });
+}
+
+/// Forward the logs of nacos-sdk to python logging, by installing the subscriber of tracing.
+/// It must be called before building any client, otherwise nacos-sdk installs its own, which writes
+/// the log files in `$HOME/logs/nacos`, and it raises. The level is from env `NACOS_CLIENT_LOGGER_LEVEL`,
+/// default INFO, and set by `set_log_level`. Noop if it is enabled already.
+/// If it fails, pay attention to err
+#[pyfunction]
+pub fn enable_log_forwarding(py: Python<'_>) -> PyResult<()> {
+    if ENABLED.load(Ordering::Acquire) {
+        return Ok(());
+    }
+    if let Ok(level) = std::env::var("NACOS_CLIENT_LOGGER_LEVEL")
+        && let Ok(level) = parse_level(&level)
+    {
+        MAX_LEVEL.store(level, Ordering::Relaxed);
+    }
+    if tracing::subscriber::set_global_default(PythonLogging).is_err() {
+        return Err(PyRuntimeError::new_err(
+            "The subscriber of tracing is installed already, by nacos-sdk on building a client \
+             or by another extension, call enable_log_forwarding before building any client",
+        ));
+    }
+    ENABLED.store(true, Ordering::Release);
+    start_forwarder();
+    let stop = wrap_pyfunction!(stop_forwarder, py)?;
+    py.import("atexit")?.call_method1("register", (stop,))?;
+    Ok(())
+}
+
+/// Set the level of the logs of nacos-sdk forwarded to python logging by `enable_log_forwarding`, one of
+/// TRACE, DEBUG, INFO, WARN, ERROR, OFF. The python loggers `nacos_sdk.*` filter them again by their levels.
+/// If it fails, pay attention to err
+#[pyfunction]
+pub fn set_log_level(level: &str) -> PyResult<()> {
+    MAX_LEVEL.store(parse_level(level)?, Ordering::Relaxed);
+    Ok(())
+}
@@ -0,0 +1,299 @@
+#![deny(clippy::all)]
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Upper bounds of the buckets of latency histograms, in seconds.
+const BUCKETS: [f64; 12] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Metrics of all clients in this process. It is leaked, and replaced in the child process after fork,
+/// where its lock may be held by a thread not existing there.
+static METRICS: AtomicPtr<Mutex<Metrics>> = AtomicPtr::new(std::ptr::null_mut());
+
+fn lock_metrics() -> MutexGuard<'static, Metrics> {
+    let mut current = METRICS.load(Ordering::Acquire);
+    if current.is_null() {
+        let new = Box::into_raw(Box::new(Mutex::new(Metrics::default())));
+        current = match METRICS.compare_exchange(
+            std::ptr::null_mut(),
+            new,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => new,
+            Err(existing) => {
+                // SAFETY: it is never shared since the exchange failed
+                drop(unsafe { Box::from_raw(new) });
+                existing
+            }
+        };
+    }
+    // SAFETY: the metrics set are leaked, so they live as long as the process
+    unsafe { &*current }.lock().unwrap()
+}
+
+/// The clients of the parent process do not work in the child process after fork, it counts from zero.
+pub(crate) fn after_fork_in_child() {
+    METRICS.store(std::ptr::null_mut(), Ordering::Release);
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// Count of each bucket, not cumulative, the last is `+Inf`
+    buckets: [u64; BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = BUCKETS
+            .iter()
+            .position(|le| seconds <= *le)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    /// Cumulative count of each bucket, e.g. `[("0.001", 1), ..., ("+Inf", 3)]`.
+    fn cumulative(&self) -> Vec<(String, u64)> {
+        let mut total = 0;
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                total += count;
+                let le = BUCKETS
+                    .get(i)
+                    .map(|le| le.to_string())
+                    .unwrap_or_else(|| "+Inf".to_string());
+                (le, total)
+            })
+            .collect()
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let buckets = PyDict::new(py);
+        for (le, count) in self.cumulative() {
+            buckets.set_item(le, count)?;
+        }
+        let dict = PyDict::new(py);
+        dict.set_item("count", self.count)?;
+        dict.set_item("sum", self.sum)?;
+        dict.set_item("buckets", buckets)?;
+        Ok(dict)
+    }
+
+    fn write_prometheus(&self, out: &mut String, name: &str, labels: &str) {
+        for (le, count) in self.cumulative() {
+            let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, le, count);
+        }
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+#[derive(Default)]
+struct RequestMetrics {
+    errors: BTreeMap<String, u64>,
+    latency: Histogram,
+}
+
+#[derive(Default)]
+struct ListenerMetrics {
+    failures: u64,
+    duration: Histogram,
+}
+
+#[derive(Default)]
+struct Metrics {
+    /// By method, e.g. get_config
+    requests: BTreeMap<&'static str, RequestMetrics>,
+    /// By listener, config or naming
+    listeners: BTreeMap<&'static str, ListenerMetrics>,
+    /// By kind, config or naming
+    subscriptions: BTreeMap<&'static str, u64>,
+}
+
+/// Observe a request to server, with the kind of err if it fails.
+pub(crate) fn observe_request(method: &'static str, elapsed: Duration, err_kind: Option<String>) {
+    let mut metrics = lock_metrics();
+    let request = metrics.requests.entry(method).or_default();
+    request.latency.observe(elapsed);
+    if let Some(err_kind) = err_kind {
+        *request.errors.entry(err_kind).or_default() += 1;
+    }
+}
+
+/// Observe a call of the listener func, config or naming.
+pub(crate) fn observe_listener(listener: &'static str, elapsed: Duration, failed: bool) {
+    let mut metrics = lock_metrics();
+    let listener = metrics.listeners.entry(listener).or_default();
+    listener.duration.observe(elapsed);
+    if failed {
+        listener.failures += 1;
+    }
+}
+
+/// A listener or subscription is added, config or naming. `remove_listener` and `unsubscribe` are noop,
+/// only the subscription of the LoadBalancers closed is removed.
+pub(crate) fn add_subscription(kind: &'static str) {
+    *lock_metrics().subscriptions.entry(kind).or_default() += 1;
+}
+
+/// A subscription is removed, config or naming.
+pub(crate) fn remove_subscription(kind: &'static str) {
+    if let Some(count) = lock_metrics().subscriptions.get_mut(kind) {
+        *count = count.saturating_sub(1);
+    }
+}
+
+/// Kind of the err of nacos-sdk, the name of its variant, e.g. `ConfigNotFound`.
+pub(crate) fn err_kind(err: &nacos_sdk::api::error::Error) -> String {
+    let debug = format!("{:?}", err);
+    debug
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Metrics of all sync and async clients in this process, as dict. e.g.
+/// `{"requests": {"get_config": {"count": 1, "errors": {"ConfigNotFound": 1}, "latency_seconds": {...}}},
+/// "listener_callbacks": {"config": {"count": 1, "failures": 0, "duration_seconds": {...}}},
+/// "active_subscriptions": {"config": 1}, "log_events_dropped": 0}`,
+/// the histograms are `{"count": 1, "sum": 0.002, "buckets": {"0.001": 0, ..., "+Inf": 1}}` with cumulative buckets.
+#[pyfunction]
+pub fn metrics(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let metrics = lock_metrics();
+
+    let requests = PyDict::new(py);
+    for (method, request) in metrics.requests.iter() {
+        let errors = PyDict::new(py);
+        for (err_kind, count) in request.errors.iter() {
+            errors.set_item(err_kind, count)?;
+        }
+        let dict = PyDict::new(py);
+        dict.set_item("count", request.latency.count)?;
+        dict.set_item("errors", errors)?;
+        dict.set_item("latency_seconds", request.latency.to_dict(py)?)?;
+        requests.set_item(method, dict)?;
+    }
+
+    let listeners = PyDict::new(py);
+    for (listener, listener_metrics) in metrics.listeners.iter() {
+        let dict = PyDict::new(py);
+        dict.set_item("count", listener_metrics.duration.count)?;
+        dict.set_item("failures", listener_metrics.failures)?;
+        dict.set_item("duration_seconds", listener_metrics.duration.to_dict(py)?)?;
+        listeners.set_item(listener, dict)?;
+    }
+
+    let subscriptions = PyDict::new(py);
+    for (kind, count) in metrics.subscriptions.iter() {
+        subscriptions.set_item(kind, count)?;
+    }
+
+    let dict = PyDict::new(py);
+    dict.set_item("requests", requests)?;
+    dict.set_item("listener_callbacks", listeners)?;
+    dict.set_item("active_subscriptions", subscriptions)?;
+    dict.set_item("log_events_dropped", crate::logging::dropped_events())?;
+    Ok(dict)
+}
+
+/// Metrics of all sync and async clients in this process, as Prometheus text exposition format,
+/// e.g. for the `/metrics` endpoint.
+#[pyfunction]
+pub fn metrics_prometheus() -> String {
+    let metrics = lock_metrics();
+    let mut out = String::new();
+
+    out.push_str("# HELP nacos_client_requests_total Requests to Nacos server.\n");
+    out.push_str("# TYPE nacos_client_requests_total counter\n");
+    for (method, request) in metrics.requests.iter() {
+        let _ = writeln!(
+            out,
+            "nacos_client_requests_total{{method=\"{}\"}} {}",
+            method, request.latency.count
+        );
+    }
+
+    out.push_str("# HELP nacos_client_request_errors_total Failed requests to Nacos server.\n");
+    out.push_str("# TYPE nacos_client_request_errors_total counter\n");
+    for (method, request) in metrics.requests.iter() {
+        for (err_kind, count) in request.errors.iter() {
+            let _ = writeln!(
+                out,
+                "nacos_client_request_errors_total{{method=\"{}\",kind=\"{}\"}} {}",
+                method, err_kind, count
+            );
+        }
+    }
+
+    out.push_str(
+        "# HELP nacos_client_request_duration_seconds Latency of requests to Nacos server.\n",
+    );
+    out.push_str("# TYPE nacos_client_request_duration_seconds histogram\n");
+    for (method, request) in metrics.requests.iter() {
+        request.latency.write_prometheus(
+            &mut out,
+            "nacos_client_request_duration_seconds",
+            &format!("method=\"{}\"", method),
+        );
+    }
+
+    out.push_str(
+        "# HELP nacos_client_listener_callback_failures_total Listener callbacks which raised.\n",
+    );
+    out.push_str("# TYPE nacos_client_listener_callback_failures_total counter\n");
+    for (listener, listener_metrics) in metrics.listeners.iter() {
+        let _ = writeln!(
+            out,
+            "nacos_client_listener_callback_failures_total{{listener=\"{}\"}} {}",
+            listener, listener_metrics.failures
+        );
+    }
+
+    out.push_str(
+        "# HELP nacos_client_listener_callback_duration_seconds Duration of listener callbacks.\n",
+    );
+    out.push_str("# TYPE nacos_client_listener_callback_duration_seconds histogram\n");
+    for (listener, listener_metrics) in metrics.listeners.iter() {
+        listener_metrics.duration.write_prometheus(
+            &mut out,
+            "nacos_client_listener_callback_duration_seconds",
+            &format!("listener=\"{}\"", listener),
+        );
+    }
+
+    out.push_str("# HELP nacos_client_active_subscriptions Listeners and subscriptions added.\n");
+    out.push_str("# TYPE nacos_client_active_subscriptions gauge\n");
+    for (kind, count) in metrics.subscriptions.iter() {
+        let _ = writeln!(
+            out,
+            "nacos_client_active_subscriptions{{kind=\"{}\"}} {}",
+            kind, count
+        );
+    }
+
+    out.push_str("# HELP nacos_client_log_events_dropped_total Logs of nacos-sdk dropped since python logging falls behind.\n");
+    out.push_str("# TYPE nacos_client_log_events_dropped_total counter\n");
+    let _ = writeln!(
+        out,
+        "nacos_client_log_events_dropped_total {}",
+        crate::logging::dropped_events()
+    );
+
+    out
+}
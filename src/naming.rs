@@ -1,203 +1,440 @@
 #![deny(clippy::all)]
 
-use pyo3::exceptions::{PyRuntimeError, PyValueError};
-use pyo3::types::PyAnyMethods;
-use pyo3::{Bound, Py, PyAny, PyErr, PyResult, Python, pyclass, pymethods};
+use pyo3::exceptions::{PyIndexError, PyRuntimeError, PyValueError};
+use pyo3::types::{PyAnyMethods, PyDict, PyDictMethods, PySlice, PySliceMethods};
+use pyo3::{Bound, IntoPyObjectExt, Py, PyAny, PyErr, PyRef, PyResult, Python, pyclass, pymethods};
 
 use std::sync::Arc;
+use std::time::Instant;
+
+use crate::connection::{ConnectionTracker, DEFAULT_HEALTH_CHECK_TIMEOUT, NacosHealthCheckResult};
+use crate::health_check::{HealthCheck, HealthCheckers};
+use crate::load_balancer::{InstanceSnapshots, LoadBalancer, Strategy};
+use crate::locality::Locality;
+use crate::open_api::OpenApi;
+use crate::otel::{ClientTracer, ListenerSpans};
+use crate::outlier::OutlierDetector;
+use crate::registration::{RegistrationManager, Registrations};
+use crate::selector::MetadataSelector;
 
 /// Client api of Nacos Naming.
 #[pyclass(module = "nacos_sdk_rust_binding_py")]
 pub struct NacosNamingClient {
     inner: nacos_sdk::api::naming::NamingService,
+    outlier_detector: Arc<OutlierDetector>,
+    locality: Locality,
+    health_checkers: Arc<HealthCheckers>,
+    registrations: Arc<Registrations>,
+    snapshots: Arc<InstanceSnapshots>,
+    connection: Arc<ConnectionTracker>,
+    open_api: Arc<OpenApi>,
+    tracer: ClientTracer,
+    built_in: crate::fork::BuiltIn,
 }
 
 #[pymethods]
 impl NacosNamingClient {
     /// Build a Naming Client.
     #[new]
-    pub fn new(client_options: crate::ClientOptions) -> PyResult<Self> {
-        let props = nacos_sdk::api::props::ClientProps::new()
-            .server_addr(client_options.server_addr)
-            .namespace(client_options.namespace)
-            .app_name(
-                client_options
-                    .app_name
-                    .unwrap_or(nacos_sdk::api::constants::UNKNOWN.to_string()),
-            )
-            .naming_push_empty_protection(
-                client_options.naming_push_empty_protection.unwrap_or(true),
-            )
-            .naming_load_cache_at_start(client_options.naming_load_cache_at_start.unwrap_or(false));
-
-        let props = if let Some(ep) = client_options.endpoint {
-            props.endpoint(ep)
-        } else {
-            props
-        };
+    pub fn new(py: Python<'_>, client_options: crate::ClientOptions) -> PyResult<Self> {
+        let outlier_detector = Arc::new(OutlierDetector::new(&client_options));
+        let locality = Locality::new(&client_options);
+        let connection = Arc::new(ConnectionTracker::new(&client_options));
+        let open_api = Arc::new(OpenApi::new(&client_options)?);
+        let tracer = ClientTracer::new(&client_options);
+        let reconcile_interval_ms = client_options.registration_reconcile_interval_ms;
 
-        // need enable_auth_plugin_http with username & password
-        let is_enable_auth_http =
-            client_options.username.is_some() && client_options.password.is_some();
-        // need enable_auth_plugin_aliyun with access_key & access_secret
-        let is_enable_auth_aliyun =
-            client_options.access_key.is_some() && client_options.access_secret.is_some();
-
-        let props = if is_enable_auth_http {
-            props
-                .auth_username(client_options.username.unwrap())
-                .auth_password(client_options.password.unwrap())
-        } else if is_enable_auth_aliyun {
-            props
-                .auth_access_key(client_options.access_key.unwrap())
-                .auth_access_secret(client_options.access_secret.unwrap())
-                .auth_signature_region_id(client_options.signature_region_id.unwrap())
-        } else {
-            props
-        };
-
-        let naming_service_builder = if is_enable_auth_http {
-            nacos_sdk::api::naming::NamingServiceBuilder::new(props).enable_auth_plugin_http()
-        } else if is_enable_auth_aliyun {
-            nacos_sdk::api::naming::NamingServiceBuilder::new(props).enable_auth_plugin_aliyun()
-        } else {
-            nacos_sdk::api::naming::NamingServiceBuilder::new(props)
+        let (props, auth_plugin) = client_options.build_client_props()?;
+        let naming_service_builder = match auth_plugin {
+            crate::AuthPlugin::Http => {
+                nacos_sdk::api::naming::NamingServiceBuilder::new(props).enable_auth_plugin_http()
+            }
+            crate::AuthPlugin::Aliyun => {
+                nacos_sdk::api::naming::NamingServiceBuilder::new(props).enable_auth_plugin_aliyun()
+            }
+            crate::AuthPlugin::None => nacos_sdk::api::naming::NamingServiceBuilder::new(props),
         };
 
-        let naming_service = crate::block_on(naming_service_builder.build())
+        let naming_service = py
+            .detach(|| crate::block_on(naming_service_builder.build()))
             .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        connection.set_probe(crate::connection::naming_probe(naming_service.clone()));
+
+        let snapshots = Arc::new(InstanceSnapshots::new(naming_service.clone()));
+        let health_checkers = Arc::new(HealthCheckers::default());
+        let registrations = Registrations::new(
+            naming_service.clone(),
+            health_checkers.clone(),
+            connection.clone(),
+            reconcile_interval_ms,
+        )?;
 
         Ok(NacosNamingClient {
             inner: naming_service,
+            outlier_detector,
+            locality,
+            health_checkers,
+            registrations,
+            snapshots,
+            connection,
+            open_api,
+            tracer,
+            built_in: crate::fork::BuiltIn::current(),
         })
     }
 
-    /// Register instance.
+    /// Register instance, with an optional health_check PyFunction which returns truthy when healthy.
+    /// It is called every health_check_interval_ms (default 5000), and it fails if not returned in
+    /// health_check_timeout_ms (default the interval), e.g. the app is deadlocked. On failure the instance is
+    /// re-registered with healthy=false or deregistered by health_check_failure_action
+    /// (unhealthy(default), deregister), and it is restored when the check passes again.
+    /// The other instances of the service registered by this client are kept.
     /// If it fails, pay attention to err
+    #[pyo3(signature = (service_name, group, service_instance, health_check=None, health_check_interval_ms=None, health_check_timeout_ms=None, health_check_failure_action=None))]
+    #[allow(clippy::too_many_arguments)]
     pub fn register_instance(
         &self,
+        py: Python<'_>,
         service_name: String,
         group: String,
         service_instance: NacosServiceInstance,
+        health_check: Option<Bound<'_, PyAny>>,
+        health_check_interval_ms: Option<u64>,
+        health_check_timeout_ms: Option<u64>,
+        health_check_failure_action: Option<String>,
     ) -> PyResult<()> {
-        let future = self.inner.register_instance(
-            service_name,
-            Some(group),
-            transfer_ffi_instance_to_rust(&service_instance),
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.register_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
         );
-        crate::block_on(future)
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+        span.in_scope(|| {
+            let health_check = HealthCheck::parse(
+                health_check,
+                health_check_interval_ms,
+                health_check_timeout_ms,
+                health_check_failure_action,
+            )?;
+            let rust_instance = transfer_ffi_instance_to_rust(&service_instance);
+            self.health_checkers
+                .stop(&service_name, &group, &rust_instance.ip, rust_instance.port);
+
+            // along with the other instances of the service registered by this client
+            let future = self
+                .registrations
+                .register(&service_name, &group, rust_instance.clone());
+            py.detach(|| crate::block_on(future))?;
+
+            if let Some(health_check) = health_check {
+                self.health_checkers.start(
+                    Arc::downgrade(&self.registrations),
+                    service_name,
+                    group,
+                    &rust_instance.ip,
+                    rust_instance.port,
+                    health_check,
+                );
+            }
+            Ok(())
+        })
     }
 
-    /// Deregister instance.
+    /// Deregister instance, the other instances of the service registered by this client are kept.
     /// If it fails, pay attention to err
     pub fn deregister_instance(
         &self,
+        py: Python<'_>,
         service_name: String,
         group: String,
         service_instance: NacosServiceInstance,
     ) -> PyResult<()> {
-        let future = self.inner.deregister_instance(
-            service_name,
-            Some(group),
-            transfer_ffi_instance_to_rust(&service_instance),
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.deregister_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
         );
-        crate::block_on(future)
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+        span.in_scope(|| {
+            // the other instances of the service registered by this client are kept
+            let rust_instance = transfer_ffi_instance_to_rust(&service_instance);
+            let future = self.registrations.deregister(
+                "deregister_instance",
+                &service_name,
+                &group,
+                std::slice::from_ref(&rust_instance),
+            );
+            py.detach(|| crate::block_on(future))
+                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+        })
     }
 
     /// Batch register instance, improve interaction efficiency.
+    /// It replaces the instances of the service registered by this client.
     /// If it fails, pay attention to err
     pub fn batch_register_instance(
         &self,
+        py: Python<'_>,
         service_name: String,
         group: String,
         service_instances: Vec<NacosServiceInstance>,
     ) -> PyResult<()> {
-        let rust_instances = service_instances
-            .iter()
-            .map(transfer_ffi_instance_to_rust)
-            .collect();
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.batch_register_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        span.in_scope(|| {
+            let rust_instances: Vec<nacos_sdk::api::naming::ServiceInstance> = service_instances
+                .iter()
+                .map(transfer_ffi_instance_to_rust)
+                .collect();
 
-        let future = self
-            .inner
-            .batch_register_instance(service_name, Some(group), rust_instances);
-        crate::block_on(future)
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))
+            // it replaces all the instances of the service registered by this client
+            let future = self
+                .registrations
+                .batch_register(&service_name, &group, rust_instances);
+            py.detach(|| crate::block_on(future))
+        })
+    }
+
+    /// Batch deregister instances of the service, the remaining instances of the service registered by
+    /// this client are published together. Returns the result of each instance, None on success, otherwise the err,
+    /// which is ValueError for the instance not registered by this client.
+    pub fn batch_deregister_instance(
+        &self,
+        py: Python<'_>,
+        service_name: String,
+        group: String,
+        service_instances: Vec<NacosServiceInstance>,
+    ) -> PyResult<Vec<Option<PyErr>>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.batch_deregister_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        span.in_scope(|| {
+            let rust_instances = service_instances
+                .iter()
+                .map(transfer_ffi_instance_to_rust)
+                .collect();
+            Ok(py.detach(|| {
+                crate::block_on(self.registrations.deregister_many(
+                    service_name,
+                    group,
+                    rust_instances,
+                ))
+            }))
+        })
+    }
+
+    /// Register instances of several services, each item is (service_name, group, service_instance),
+    /// the ones of a service are published together, it continues on error of a service.
+    /// Returns the result of each item, None on success, otherwise the err of its service.
+    pub fn register_many(
+        &self,
+        py: Python<'_>,
+        registrations: Vec<(String, String, NacosServiceInstance)>,
+    ) -> PyResult<Vec<Option<PyErr>>> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span("nacos.register_many", &[]);
+        span.in_scope(|| {
+            let rust_registrations = registrations
+                .iter()
+                .map(|(service_name, group, service_instance)| {
+                    (
+                        service_name.clone(),
+                        group.clone(),
+                        transfer_ffi_instance_to_rust(service_instance),
+                    )
+                })
+                .collect();
+            Ok(py.detach(|| crate::block_on(self.registrations.register_many(rust_registrations))))
+        })
+    }
+
+    /// Update the registered instance in place, e.g. weight, metadata, or enabled=False / weight=0
+    /// to drain traffic. It re-registers without deregister, so the consumers see no flap.
+    /// It raises ValueError when the instance is not registered by this client or does not exist in server.
+    /// If it fails, pay attention to err
+    pub fn update_instance(
+        &self,
+        py: Python<'_>,
+        service_name: String,
+        group: String,
+        service_instance: NacosServiceInstance,
+    ) -> PyResult<()> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.update_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        span.in_scope(|| {
+            let future = self.registrations.update_instance(
+                service_name,
+                group,
+                transfer_ffi_instance_to_rust(&service_instance),
+            );
+            py.detach(|| crate::block_on(future))
+        })
     }
 
     /// Get all instances by service and group. default cluster=[], subscribe=true.
+    /// metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
     /// If it fails, pay attention to err
-    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None))]
+    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None, metadata_selector=None))]
     pub fn get_all_instances(
         &self,
+        py: Python<'_>,
         service_name: String,
         group: String,
         clusters: Option<Vec<String>>,
         subscribe: Option<bool>,
-    ) -> PyResult<Vec<NacosServiceInstance>> {
-        let future = self.inner.get_all_instances(
-            service_name,
-            Some(group),
-            clusters.unwrap_or_default(),
-            subscribe.unwrap_or(true),
+        metadata_selector: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<NacosServiceInstanceList> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.get_all_instances",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
         );
-        let rust_instances = crate::block_on(future)
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        span.in_scope(|| {
+            let metadata_selector = MetadataSelector::parse(metadata_selector)?;
+            let future = self.inner.get_all_instances(
+                service_name,
+                Some(group),
+                clusters.unwrap_or_default(),
+                subscribe.unwrap_or(true),
+            );
+            let rust_instances = py
+                .detach(|| crate::block_on(self.connection.track("get_all_instances", future)))
+                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+            let rust_instances = metadata_selector.filter(rust_instances);
 
-        Ok(rust_instances
-            .iter()
-            .map(transfer_rust_instance_to_ffi)
-            .collect())
+            Ok(NacosServiceInstanceList::from_rust(Arc::new(
+                rust_instances,
+            )))
+        })
     }
 
     /// Select instances whether healthy or not. default cluster=[], subscribe=true, healthy=true.
+    /// metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
     /// If it fails, pay attention to err
-    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None, healthy=None))]
+    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None, healthy=None, metadata_selector=None))]
+    #[allow(clippy::too_many_arguments)]
     pub fn select_instances(
         &self,
+        py: Python<'_>,
         service_name: String,
         group: String,
         clusters: Option<Vec<String>>,
         subscribe: Option<bool>,
         healthy: Option<bool>,
-    ) -> PyResult<Vec<NacosServiceInstance>> {
-        let future = self.inner.select_instances(
-            service_name,
-            Some(group),
-            clusters.unwrap_or_default(),
-            subscribe.unwrap_or(true),
-            healthy.unwrap_or(true),
+        metadata_selector: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<NacosServiceInstanceList> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.select_instances",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
         );
-        let rust_instances = crate::block_on(future)
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        span.in_scope(|| {
+            let metadata_selector = MetadataSelector::parse(metadata_selector)?;
+            let healthy = healthy.unwrap_or(true);
+            let future = self.inner.select_instances(
+                service_name,
+                Some(group),
+                clusters.unwrap_or_default(),
+                subscribe.unwrap_or(true),
+                healthy,
+            );
+            let rust_instances = py
+                .detach(|| crate::block_on(self.connection.track("select_instances", future)))
+                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+            let rust_instances = metadata_selector.filter(rust_instances);
+            // the ejected instances are not healthy
+            let rust_instances = if healthy {
+                self.outlier_detector.filter(rust_instances)
+            } else {
+                rust_instances
+            };
 
-        Ok(rust_instances
-            .iter()
-            .map(transfer_rust_instance_to_ffi)
-            .collect())
+            Ok(NacosServiceInstanceList::from_rust(Arc::new(
+                rust_instances,
+            )))
+        })
     }
 
     /// Select one healthy instance. default cluster=[], subscribe=true.
+    /// metadata_selector is a dict, or an expression e.g. `version in (1,2),zone!=b`, which filters by metadata.
+    /// It prefers the instances in the same zone or cluster, by locality_zone and locality_cluster
+    /// of the args or ClientOptions, and falls back to all the zones when the healthy local
+    /// instances are below locality_min_healthy_percent.
     /// If it fails, pay attention to err
-    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None))]
+    #[pyo3(signature = (service_name, group, clusters=None, subscribe=None, metadata_selector=None, locality_zone=None, locality_cluster=None))]
+    #[allow(clippy::too_many_arguments)]
     pub fn select_one_healthy_instance(
         &self,
+        py: Python<'_>,
         service_name: String,
         group: String,
         clusters: Option<Vec<String>>,
         subscribe: Option<bool>,
+        metadata_selector: Option<Bound<'_, PyAny>>,
+        locality_zone: Option<String>,
+        locality_cluster: Option<String>,
     ) -> PyResult<NacosServiceInstance> {
-        let future = self.inner.select_one_healthy_instance(
-            service_name,
-            Some(group),
-            clusters.unwrap_or_default(),
-            subscribe.unwrap_or(true),
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.select_one_healthy_instance",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
         );
-        let rust_instance = crate::block_on(future)
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        span.in_scope(|| {
+            let metadata_selector = MetadataSelector::parse(metadata_selector)?;
+            let locality = self.locality.with(locality_zone, locality_cluster);
+            // all instances, the unhealthy local ones count for the locality
+            let future = self.inner.get_all_instances(
+                service_name.clone(),
+                Some(group.clone()),
+                clusters.unwrap_or_default(),
+                subscribe.unwrap_or(true),
+            );
+            let rust_instances = py
+                .detach(|| {
+                    crate::block_on(self.connection.track("select_one_healthy_instance", future))
+                })
+                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+            let rust_instances = locality.prefer_local(
+                metadata_selector.filter(rust_instances),
+                &self.outlier_detector,
+            );
+            let rust_instance = self
+                .outlier_detector
+                .select_one(rust_instances)
+                .ok_or_else(|| no_healthy_instance_err(&service_name, &group))?;
 
-        Ok(transfer_rust_instance_to_ffi(&rust_instance))
+            Ok(transfer_rust_instance_to_ffi(&rust_instance))
+        })
     }
 
     /// Add NacosNamingEventListener callback func, which listen the instance change.
@@ -205,26 +442,166 @@ impl NacosNamingClient {
     #[pyo3(signature = (service_name, group, clusters, listener))]
     pub fn subscribe<'p>(
         &self,
+        py: Python<'_>,
         service_name: String,
         group: String,
         clusters: Option<Vec<String>>,
         listener: Bound<'p, PyAny>, // PyFunction arg: Vec<NacosServiceInstance>
     ) -> PyResult<()> {
-        if !listener.is_callable() {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.subscribe",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        span.in_scope(|| {
+            if !listener.is_callable() {
+                return Err(PyErr::new::<PyValueError, _>(
+                    "Arg `listener` must be a callable",
+                ));
+            }
+            let listen_wrap = Arc::new(NacosNamingEventListener {
+                func: Arc::new(listener.into()),
+                spans: self.tracer.listener_spans(
+                    "nacos.naming.notify",
+                    &[
+                        ("nacos.service_name", &service_name),
+                        ("nacos.group", &group),
+                    ],
+                    &span,
+                ),
+            });
+            let future = self.inner.subscribe(
+                service_name,
+                Some(group),
+                clusters.unwrap_or_default(),
+                listen_wrap,
+            );
+            py.detach(|| crate::block_on(self.connection.track("subscribe", future)))
+                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+            crate::metrics::add_subscription("naming");
+            Ok(())
+        })
+    }
+
+    /// Build a LoadBalancer of service, which keeps a local instance snapshot updated through subscribe.
+    /// The snapshot is shared by the LoadBalancers of the same service, group and clusters, until all of them are closed.
+    /// strategy: weighted_random(default), round_robin, least_recently_used, consistent_hash.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (service_name, group, clusters=None, strategy=None))]
+    pub fn load_balancer(
+        &self,
+        py: Python<'_>,
+        service_name: String,
+        group: String,
+        clusters: Option<Vec<String>>,
+        strategy: Option<String>,
+    ) -> PyResult<LoadBalancer> {
+        self.built_in.check()?;
+        let span = self.tracer.start_span(
+            "nacos.load_balancer",
+            &[
+                ("nacos.service_name", &service_name),
+                ("nacos.group", &group),
+            ],
+        );
+        span.in_scope(|| {
+            let strategy = Strategy::parse(strategy)?;
+            let clusters = clusters.unwrap_or_default();
+            let future =
+                self.snapshots
+                    .acquire(service_name.clone(), group.clone(), clusters.clone());
+            let snapshot = py
+                .detach(|| crate::block_on(self.connection.track("load_balancer", future)))
+                .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+
+            Ok(LoadBalancer::new(
+                service_name,
+                group,
+                clusters,
+                strategy,
+                snapshot,
+                self.snapshots.clone(),
+                self.outlier_detector.clone(),
+            ))
+        })
+    }
+
+    /// Report a successful call to the instance, which resets its consecutive failures.
+    pub fn report_success(&self, service_instance: NacosServiceInstance) {
+        self.outlier_detector
+            .report_success(&service_instance.ip, service_instance.port);
+    }
+
+    /// Report a failed call to the instance, it will be ejected from the select results
+    /// temporarily when the consecutive failures exceed the threshold.
+    pub fn report_failure(&self, service_instance: NacosServiceInstance) {
+        self.outlier_detector
+            .report_failure(&service_instance.ip, service_instance.port);
+    }
+
+    /// Registration manager of the instances registered through this client.
+    #[getter]
+    pub fn registration_manager(&self) -> RegistrationManager {
+        RegistrationManager::new(self.registrations.clone(), self.built_in, false)
+    }
+
+    /// Close the client, deregister all the instances registered through it.
+    /// If it fails, pay attention to err
+    pub fn close(&self, py: Python<'_>) -> PyResult<()> {
+        self.built_in.check()?;
+        py.detach(|| crate::block_on(self.registrations.close()))
+    }
+
+    /// Check the server by a round trip of a request over the connection of this client, so its auth
+    /// plugin is checked as well, e.g. for the readiness probe. Returns NacosHealthCheckResult with
+    /// the latency, server address, server version and auth status. The server version is
+    /// best-effort, by the open api under context_path. timeout is in seconds, default 3.
+    /// If it fails, pay attention to err
+    #[pyo3(signature = (timeout=None))]
+    pub fn health_check(
+        &self,
+        py: Python<'_>,
+        timeout: Option<f64>,
+    ) -> PyResult<NacosHealthCheckResult> {
+        self.built_in.check()?;
+        let timeout = timeout
+            .map(crate::parse_timeout)
+            .transpose()?
+            .unwrap_or(DEFAULT_HEALTH_CHECK_TIMEOUT);
+        // release the GIL while waiting, so the other python threads keep running
+        Ok(py.detach(|| crate::block_on(self.connection.health_check(&self.open_api, timeout))))
+    }
+
+    /// State of the connection with server: connecting, connected, disconnected or shutdown.
+    /// It is observed from the results of the requests of this client, and since it is read or called
+    /// back, of a probe request every 5s while no request is answered. Only answered ones count as connected.
+    #[getter]
+    pub fn connection_state(&self) -> &'static str {
+        self.connection.observe();
+        self.connection.state().as_str()
+    }
+
+    /// Address of the server of the gRPC connection while connected, None otherwise. nacos-sdk does
+    /// not expose which server it connects to, so it is only known when server_addr is a single server.
+    #[getter]
+    pub fn server_addr(&self) -> Option<String> {
+        self.connection.server_addr()
+    }
+
+    /// Add the callback of connection state change, which is called with the new state,
+    /// one of connecting, connected, disconnected or shutdown. Its exception is reported by sys.unraisablehook.
+    /// If it fails, pay attention to err
+    pub fn on_connection_change(&self, callback: Bound<'_, PyAny>) -> PyResult<()> {
+        if !callback.is_callable() {
             return Err(PyErr::new::<PyValueError, _>(
-                "Arg `listener` must be a callable",
+                "Arg `callback` must be a callable",
             ));
         }
-        let future = self.inner.subscribe(
-            service_name,
-            Some(group),
-            clusters.unwrap_or_default(),
-            Arc::new(NacosNamingEventListener {
-                func: Arc::new(listener.into()),
-            }),
-        );
-        crate::block_on(future)
-            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        self.connection.add_callback(callback.unbind());
+        self.connection.observe();
         Ok(())
     }
 
@@ -240,12 +617,21 @@ impl NacosNamingClient {
         clusters: Option<Vec<String>>,
         listener: Bound<'p, PyAny>, // PyFunction arg: Vec<NacosServiceInstance>
     ) -> PyResult<()> {
+        self.built_in.check()?;
         Ok(())
     }
 }
 
+pub(crate) fn no_healthy_instance_err(service_name: &str, group: &str) -> PyErr {
+    PyRuntimeError::new_err(format!(
+        "No healthy instance of service `{}` in group `{}`",
+        service_name, group
+    ))
+}
+
 pub(crate) struct NacosNamingEventListener {
     pub(crate) func: Arc<Py<PyAny>>,
+    pub(crate) spans: ListenerSpans,
 }
 
 impl nacos_sdk::api::naming::NamingEventListener for NacosNamingEventListener {
@@ -254,23 +640,23 @@ impl nacos_sdk::api::naming::NamingEventListener for NacosNamingEventListener {
             return;
         }
 
-        let rust_instances = event.instances.clone().unwrap();
-
-        let ffi_instances: Vec<NacosServiceInstance> = rust_instances
-            .iter()
-            .map(transfer_rust_instance_to_ffi)
-            .collect();
+        // the instances of event are shared, NacosServiceInstance is created lazily by the listener
+        let ffi_instances = NacosServiceInstanceList {
+            instances: SharedInstances::Event(event),
+        };
 
         // call PyFunction with args
         let _ = Python::attach(|py| -> PyResult<()> {
-            let _ = self.func.call(py, (ffi_instances,), None);
+            let started = Instant::now();
+            let result = self.spans.call(py, &self.func, (ffi_instances,));
+            crate::metrics::observe_listener("naming", started.elapsed(), result.is_err());
             Ok(())
         });
     }
 }
 
 #[pyclass(module = "nacos_sdk_rust_binding_py", from_py_object)]
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct NacosServiceInstance {
     /// Instance Id
     #[pyo3(set, get)]
@@ -333,6 +719,274 @@ impl NacosServiceInstance {
             metadata,
         })
     }
+
+    /// Convert to dict, the keys are the field names.
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("instance_id", self.instance_id.clone())?;
+        dict.set_item("ip", self.ip.clone())?;
+        dict.set_item("port", self.port)?;
+        dict.set_item("weight", self.weight)?;
+        dict.set_item("healthy", self.healthy)?;
+        dict.set_item("enabled", self.enabled)?;
+        dict.set_item("ephemeral", self.ephemeral)?;
+        dict.set_item("cluster_name", self.cluster_name.clone())?;
+        dict.set_item("service_name", self.service_name.clone())?;
+        dict.set_item("metadata", self.metadata.clone())?;
+        Ok(dict)
+    }
+
+    /// Build from dict, which is the same as the result of `to_dict()`. `ip` and `port` are required.
+    #[staticmethod]
+    pub fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<NacosServiceInstance> {
+        Ok(Self {
+            instance_id: crate::extract_dict_item(dict, "instance_id")?,
+            ip: crate::extract_dict_item_required(dict, "ip")?,
+            port: crate::extract_dict_item_required(dict, "port")?,
+            weight: crate::extract_dict_item(dict, "weight")?,
+            healthy: crate::extract_dict_item(dict, "healthy")?,
+            enabled: crate::extract_dict_item(dict, "enabled")?,
+            ephemeral: crate::extract_dict_item(dict, "ephemeral")?,
+            cluster_name: crate::extract_dict_item(dict, "cluster_name")?,
+            service_name: crate::extract_dict_item(dict, "service_name")?,
+            metadata: crate::extract_dict_item(dict, "metadata")?,
+        })
+    }
+
+    pub fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        crate::repr_fields("NacosServiceInstance", &self.to_dict(py)?)
+    }
+
+    /// Equal by all fields. It is mutable, so it is not hashable.
+    pub fn __eq__(&self, other: PyRef<'_, Self>) -> bool {
+        *self == *other
+    }
+
+    pub fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    /// Support pickle, e.g. send to multiprocessing workers.
+    pub fn __reduce__<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<(Bound<'py, PyAny>, (Bound<'py, PyDict>,))> {
+        let from_dict = py.get_type::<Self>().getattr("from_dict")?;
+        Ok((from_dict, (self.to_dict(py)?,)))
+    }
+}
+
+/// Read-only sequence of NacosServiceInstance, backed by the instances of Rust.
+/// The NacosServiceInstance is created only on index or iteration, which is much cheaper than
+/// converting all of a service with thousands of instances. Use `to_list()` for a list.
+#[pyclass(module = "nacos_sdk_rust_binding_py", sequence)]
+pub struct NacosServiceInstanceList {
+    instances: SharedInstances,
+}
+
+impl NacosServiceInstanceList {
+    pub(crate) fn from_rust(instances: Arc<Vec<nacos_sdk::api::naming::ServiceInstance>>) -> Self {
+        Self {
+            instances: SharedInstances::Vec(instances),
+        }
+    }
+}
+
+/// The instances shared without copying, of a Vec or of the event pushed to a subscription.
+#[derive(Clone)]
+enum SharedInstances {
+    Vec(Arc<Vec<nacos_sdk::api::naming::ServiceInstance>>),
+    Event(Arc<nacos_sdk::api::naming::NamingChangeEvent>),
+}
+
+impl std::ops::Deref for SharedInstances {
+    type Target = [nacos_sdk::api::naming::ServiceInstance];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            SharedInstances::Vec(instances) => instances,
+            SharedInstances::Event(event) => event.instances.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+#[pymethods]
+impl NacosServiceInstanceList {
+    #[new]
+    pub fn new(service_instances: Vec<NacosServiceInstance>) -> Self {
+        Self::from_rust(Arc::new(
+            service_instances
+                .iter()
+                .map(transfer_ffi_instance_to_rust)
+                .collect(),
+        ))
+    }
+
+    /// Convert all to a list of NacosServiceInstance.
+    pub fn to_list(&self) -> Vec<NacosServiceInstance> {
+        self.instances
+            .iter()
+            .map(transfer_rust_instance_to_ffi)
+            .collect()
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// By index, negative index is supported. A slice returns a list.
+    pub fn __getitem__<'py>(
+        &self,
+        py: Python<'py>,
+        index: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        if let Ok(slice) = index.cast::<PySlice>() {
+            let indices = slice.indices(self.instances.len() as isize)?;
+            let mut items = Vec::with_capacity(indices.slicelength);
+            let mut i = indices.start;
+            for _ in 0..indices.slicelength {
+                items.push(transfer_rust_instance_to_ffi(&self.instances[i as usize]));
+                i += indices.step;
+            }
+            return items.into_bound_py_any(py);
+        }
+
+        let index: isize = index.extract()?;
+        let len = self.instances.len() as isize;
+        let position = if index < 0 { index + len } else { index };
+        if position < 0 || position >= len {
+            return Err(PyIndexError::new_err(
+                "NacosServiceInstanceList index out of range",
+            ));
+        }
+        transfer_rust_instance_to_ffi(&self.instances[position as usize]).into_bound_py_any(py)
+    }
+
+    pub fn __iter__(&self) -> NacosServiceInstanceListIterator {
+        NacosServiceInstanceListIterator {
+            instances: self.instances.clone(),
+            index: 0,
+            reversed: false,
+        }
+    }
+
+    pub fn __reversed__(&self) -> NacosServiceInstanceListIterator {
+        NacosServiceInstanceListIterator {
+            instances: self.instances.clone(),
+            index: 0,
+            reversed: true,
+        }
+    }
+
+    /// Whether the instance of the same ip, port and cluster_name is in it, the other fields are not compared.
+    pub fn __contains__(&self, value: &Bound<'_, PyAny>) -> bool {
+        self.position(value, 0, self.instances.len()).is_some()
+    }
+
+    /// The first index of the instance of the same ip, port and cluster_name in [start, stop),
+    /// negative index is supported as list.
+    #[pyo3(signature = (value, start=0, stop=None))]
+    pub fn index(
+        &self,
+        value: &Bound<'_, PyAny>,
+        start: isize,
+        stop: Option<isize>,
+    ) -> PyResult<usize> {
+        let len = self.instances.len() as isize;
+        let clamp = |i: isize| (if i < 0 { i + len } else { i }).clamp(0, len) as usize;
+        let start = clamp(start);
+        let stop = clamp(stop.unwrap_or(len));
+        self.position(value, start, stop).ok_or_else(|| {
+            PyErr::new::<PyValueError, _>("NacosServiceInstance is not in NacosServiceInstanceList")
+        })
+    }
+
+    /// Count of the instances of the same ip, port and cluster_name.
+    pub fn count(&self, value: &Bound<'_, PyAny>) -> usize {
+        let Some(identity) = InstanceIdentity::of(value) else {
+            return 0;
+        };
+        self.instances
+            .iter()
+            .filter(|instance| identity.matches(instance))
+            .count()
+    }
+
+    pub fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let list = self.to_list().into_bound_py_any(py)?;
+        Ok(format!("NacosServiceInstanceList({})", list.repr()?))
+    }
+
+    /// Equal to the list or NacosServiceInstanceList of the same instances.
+    pub fn __eq__(&self, py: Python<'_>, other: &Bound<'_, PyAny>) -> PyResult<bool> {
+        self.to_list().into_bound_py_any(py)?.eq(other)
+    }
+
+    /// Support pickle, it is unpickled from the list.
+    pub fn __reduce__<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<(Bound<'py, PyAny>, (Vec<NacosServiceInstance>,))> {
+        Ok((py.get_type::<Self>().into_any(), (self.to_list(),)))
+    }
+}
+
+impl NacosServiceInstanceList {
+    fn position(&self, value: &Bound<'_, PyAny>, start: usize, stop: usize) -> Option<usize> {
+        let identity = InstanceIdentity::of(value)?;
+        (start..stop).find(|i| identity.matches(&self.instances[*i]))
+    }
+}
+
+/// Identity of an instance in a service, ip, port and cluster_name, None of which is the default cluster.
+struct InstanceIdentity {
+    ip: String,
+    port: i32,
+    cluster_name: Option<String>,
+}
+
+impl InstanceIdentity {
+    /// None if the value is not a NacosServiceInstance.
+    fn of(value: &Bound<'_, PyAny>) -> Option<Self> {
+        let instance = value.cast::<NacosServiceInstance>().ok()?.borrow();
+        Some(Self {
+            ip: instance.ip.clone(),
+            port: instance.port,
+            cluster_name: instance.cluster_name.clone(),
+        })
+    }
+
+    fn matches(&self, instance: &nacos_sdk::api::naming::ServiceInstance) -> bool {
+        self.ip == instance.ip
+            && self.port == instance.port
+            && self.cluster_name.as_deref().unwrap_or("DEFAULT")
+                == instance.cluster_name.as_deref().unwrap_or("DEFAULT")
+    }
+}
+
+#[pyclass(module = "nacos_sdk_rust_binding_py")]
+pub struct NacosServiceInstanceListIterator {
+    instances: SharedInstances,
+    index: usize,
+    reversed: bool,
+}
+
+#[pymethods]
+impl NacosServiceInstanceListIterator {
+    pub fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    pub fn __next__(&mut self) -> Option<NacosServiceInstance> {
+        let position = if self.reversed {
+            self.instances.len().checked_sub(self.index + 1)?
+        } else {
+            self.index
+        };
+        let instance = self.instances.get(position)?;
+        self.index += 1;
+        Some(transfer_rust_instance_to_ffi(instance))
+    }
 }
 
 pub(crate) fn transfer_ffi_instance_to_rust(
@@ -0,0 +1,303 @@
+#![deny(clippy::all)]
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::{PyErr, PyResult};
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default context path of the open api of Nacos server.
+const DEFAULT_CONTEXT_PATH: &str = "/nacos";
+/// Default port of the endpoint, and of the servers resolved by it without a port.
+const DEFAULT_ENDPOINT_PORT: u16 = 8080;
+const DEFAULT_SERVER_PORT: u16 = 8848;
+/// Interval of resolving the servers by endpoint again, 30s
+const ENDPOINT_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// Timeout of resolving the servers by endpoint, 3s
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A server of the open api.
+#[derive(Clone)]
+struct Server {
+    /// Address to report, e.g. `127.0.0.1:8848`
+    addr: String,
+    /// e.g. `https://127.0.0.1:8848/nacos`
+    base_url: String,
+    http: reqwest::Client,
+}
+
+/// TLS of the http client, the same as the gRPC connection of nacos-sdk with the feature `tls`.
+#[derive(Clone)]
+struct Tls {
+    /// CA of env `NACOS_CLIENT_TLS_CA_CERT`, default the system roots
+    ca_cert: Option<reqwest::Certificate>,
+}
+
+/// The open api of Nacos server by http, for what the SDK does not expose.
+pub(crate) struct OpenApi {
+    tls: Option<Tls>,
+    context_path: String,
+    /// Servers of server_addr, the fallback of endpoint
+    servers: Vec<Server>,
+    /// Url of the server list, e.g. `http://localhost:8080/nacos/serverlist`
+    endpoint: Option<String>,
+    /// Servers resolved by endpoint, and when
+    resolved: tokio::sync::Mutex<Option<(Vec<Server>, Instant)>>,
+    username: Option<String>,
+    password: Option<String>,
+    /// Cached access token of login, and when to login again
+    token: Mutex<Option<(String, Instant)>>,
+    /// The server which answered the state api last time
+    answered: Mutex<Option<Server>>,
+}
+
+impl OpenApi {
+    /// If it fails, pay attention to err
+    pub(crate) fn new(client_options: &crate::ClientOptions) -> PyResult<Self> {
+        let tls = if cfg!(feature = "tls") {
+            let ca_cert =
+                match std::env::var(nacos_sdk::api::constants::ENV_NACOS_CLIENT_TLS_CA_CERT) {
+                    Ok(path) => {
+                        let pem = std::fs::read(&path).map_err(|io_err| {
+                            PyErr::new::<PyValueError, _>(format!("Read {}: {:?}", path, &io_err))
+                        })?;
+                        Some(reqwest::Certificate::from_pem(&pem).map_err(|http_err| {
+                            PyErr::new::<PyValueError, _>(format!("{:?}", &http_err))
+                        })?)
+                    }
+                    Err(_) => None,
+                };
+            Some(Tls { ca_cert })
+        } else {
+            None
+        };
+
+        let context_path = client_options
+            .context_path
+            .as_deref()
+            .unwrap_or(DEFAULT_CONTEXT_PATH)
+            .trim_matches('/');
+        let context_path = if context_path.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", context_path)
+        };
+
+        // endpoint takes precedence, the same as the SDK
+        let endpoint = client_options.endpoint.as_deref().map(|endpoint| {
+            let endpoint = endpoint.trim().trim_end_matches('/');
+            if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+                endpoint.to_string()
+            } else if endpoint.contains(':') {
+                format!("http://{}/nacos/serverlist", endpoint)
+            } else {
+                format!(
+                    "http://{}:{}/nacos/serverlist",
+                    endpoint, DEFAULT_ENDPOINT_PORT
+                )
+            }
+        });
+
+        let mut open_api = Self {
+            tls,
+            context_path,
+            servers: Vec::new(),
+            endpoint,
+            resolved: tokio::sync::Mutex::new(None),
+            username: client_options.username.clone(),
+            password: client_options.password.clone(),
+            token: Mutex::new(None),
+            answered: Mutex::new(None),
+        };
+        // e.g. `127.0.0.1:8848,127.0.0.1:8849`
+        open_api.servers = client_options
+            .server_addr
+            .split(',')
+            .map(str::trim)
+            .filter(|addr| !addr.is_empty())
+            .map(|addr| open_api.server(addr))
+            .collect::<reqwest::Result<_>>()
+            .map_err(|http_err| PyErr::new::<PyValueError, _>(format!("{:?}", &http_err)))?;
+        Ok(open_api)
+    }
+
+    /// The server of addr, `https` when TLS is enabled unless the scheme is given.
+    fn server(&self, addr: &str) -> reqwest::Result<Server> {
+        let addr = addr.trim_end_matches('/');
+        let (scheme, host_port) = if let Some(host_port) = addr.strip_prefix("http://") {
+            ("http", host_port)
+        } else if let Some(host_port) = addr.strip_prefix("https://") {
+            ("https", host_port)
+        } else if self.tls.is_some() {
+            ("https", addr)
+        } else {
+            ("http", addr)
+        };
+
+        let mut builder = reqwest::Client::builder();
+        if let Some(ca_cert) = self.tls.as_ref().and_then(|tls| tls.ca_cert.clone()) {
+            builder = builder.add_root_certificate(ca_cert);
+        }
+
+        Ok(Server {
+            addr: addr.to_string(),
+            base_url: format!("{}://{}{}", scheme, host_port, self.context_path),
+            http: builder.build()?,
+        })
+    }
+
+    /// Servers to request, resolved by endpoint if it is set, otherwise of server_addr.
+    async fn servers(&self) -> Vec<Server> {
+        let Some(endpoint) = &self.endpoint else {
+            return self.servers.clone();
+        };
+        let mut resolved = self.resolved.lock().await;
+        if let Some((servers, at)) = &*resolved
+            && at.elapsed() < ENDPOINT_REFRESH_INTERVAL
+        {
+            return servers.clone();
+        }
+        match self.resolve(endpoint).await {
+            Ok(servers) if !servers.is_empty() => {
+                *resolved = Some((servers.clone(), Instant::now()));
+                servers
+            }
+            // the last resolved ones, or the fallback of server_addr
+            _ => match &*resolved {
+                Some((servers, _)) => servers.clone(),
+                None => self.servers.clone(),
+            },
+        }
+    }
+
+    /// The server list of endpoint, one `ip:port` or `ip` per line.
+    async fn resolve(&self, endpoint: &str) -> reqwest::Result<Vec<Server>> {
+        let server_list = reqwest::Client::new()
+            .get(endpoint)
+            .timeout(RESOLVE_TIMEOUT)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)?
+            .text()
+            .await?;
+        server_list
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                if line.contains(':') {
+                    self.server(line)
+                } else {
+                    self.server(&format!("{}:{}", line, DEFAULT_SERVER_PORT))
+                }
+            })
+            .collect()
+    }
+
+    /// The server which answered the state api last time, or the first one.
+    async fn current(&self) -> Option<Server> {
+        if let Some(answered) = self.answered.lock().unwrap().clone() {
+            return Some(answered);
+        }
+        self.servers().await.into_iter().next()
+    }
+
+    /// The server which answered the state api last time, None before that.
+    pub(crate) fn server_addr(&self) -> Option<String> {
+        self.answered
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|answered| answered.addr.clone())
+    }
+
+    /// GET the open api, e.g. `/v1/cs/configs`, with the access token of username & password
+    /// if they are set.
+    /// If it fails, pay attention to err
+    pub(crate) async fn get(&self, path: &str) -> PyResult<reqwest::RequestBuilder> {
+        let server = self.current().await.ok_or_else(|| {
+            PyRuntimeError::new_err("No server of server_addr or endpoint to request")
+        })?;
+        let request = server.http.get(format!("{}{}", server.base_url, path));
+        match self.token(&server).await.map_err(PyRuntimeError::new_err)? {
+            Some(access_token) => Ok(request.query(&[("accessToken", access_token)])),
+            None => Ok(request),
+        }
+    }
+
+    /// Login by username & password, returns the access token and its ttl.
+    async fn login(
+        server: &Server,
+        username: &str,
+        password: &str,
+    ) -> reqwest::Result<Option<(String, Duration)>> {
+        let login: serde_json::Value = server
+            .http
+            .post(format!("{}/v1/auth/login", server.base_url))
+            .form(&[("username", username), ("password", password)])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)?
+            .json()
+            .await?;
+        Ok(login["accessToken"].as_str().map(|access_token| {
+            let ttl = Duration::from_secs(login["tokenTtl"].as_u64().unwrap_or_default());
+            (access_token.to_string(), ttl)
+        }))
+    }
+
+    /// The cached access token, or login to the server again when it is about to expire.
+    async fn token(&self, server: &Server) -> Result<Option<String>, String> {
+        let (Some(username), Some(password)) = (&self.username, &self.password) else {
+            return Ok(None);
+        };
+        if let Some((access_token, refresh_at)) = &*self.token.lock().unwrap()
+            && Instant::now() < *refresh_at
+        {
+            return Ok(Some(access_token.clone()));
+        }
+        match Self::login(server, username, password).await {
+            Ok(Some((access_token, ttl))) => {
+                // login again at 90% of ttl, as the SDK does
+                let refresh_at = Instant::now() + ttl - ttl / 10;
+                *self.token.lock().unwrap() = Some((access_token.clone(), refresh_at));
+                Ok(Some(access_token))
+            }
+            Ok(None) => Err("No accessToken in the response of login".to_string()),
+            Err(http_err) => Err(format!("{:?}", &http_err)),
+        }
+    }
+
+    /// Round trip to the server state api, returns the version of server.
+    async fn state_version(server: &Server) -> reqwest::Result<Option<String>> {
+        let state: serde_json::Value = server
+            .http
+            .get(format!("{}/v1/console/server/state", server.base_url))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)?
+            .json()
+            .await?;
+        Ok(state["version"].as_str().map(str::to_string))
+    }
+
+    /// Version of server by the state api, which needs no login, the server answered last time first.
+    /// It is best-effort, None if no server answers.
+    pub(crate) async fn server_version(&self) -> Option<String> {
+        let mut servers = self.servers().await;
+        let answered = self.server_addr();
+        if let Some(index) = servers
+            .iter()
+            .position(|server| Some(&server.addr) == answered.as_ref())
+        {
+            servers[..=index].rotate_right(1);
+        }
+        for server in servers {
+            if let Ok(server_version) = Self::state_version(&server).await {
+                *self.answered.lock().unwrap() = Some(server);
+                return server_version;
+            }
+        }
+        None
+    }
+}
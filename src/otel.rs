@@ -0,0 +1,235 @@
+#![deny(clippy::all)]
+
+use pyo3::call::PyCallArgs;
+use pyo3::prelude::*;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyDict, PyList};
+
+/// The tracer of OpenTelemetry, set by `enable_opentelemetry`. The spans are noop without it.
+static TRACER: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+/// Enable the spans of OpenTelemetry for the calls of all clients, e.g. `nacos.get_config`.
+/// They are parented to the current context of OpenTelemetry, and exported by the tracer_provider,
+/// default the global one. It needs the package `opentelemetry-api`.
+/// If it fails, pay attention to err
+#[pyfunction]
+#[pyo3(signature = (tracer_provider=None))]
+pub fn enable_opentelemetry(py: Python<'_>, tracer_provider: Option<Py<PyAny>>) -> PyResult<()> {
+    TRACER.get_or_try_init(py, || -> PyResult<Py<PyAny>> {
+        let tracer = py.import("opentelemetry.trace")?.call_method1(
+            "get_tracer",
+            (
+                "nacos_sdk_rust_binding_py",
+                env!("CARGO_PKG_VERSION"),
+                tracer_provider,
+            ),
+        )?;
+        Ok(tracer.unbind())
+    })?;
+    Ok(())
+}
+
+fn attributes_dict<'py>(
+    py: Python<'py>,
+    namespace: &str,
+    attributes: &[(&'static str, &str)],
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("nacos.namespace", namespace)?;
+    for (key, value) in attributes {
+        dict.set_item(*key, *value)?;
+    }
+    Ok(dict)
+}
+
+/// Tracer of the calls of a client, with the namespace of it.
+pub(crate) struct ClientTracer {
+    namespace: String,
+}
+
+impl ClientTracer {
+    pub(crate) fn new(client_options: &crate::ClientOptions) -> Self {
+        Self {
+            namespace: client_options.namespace.clone(),
+        }
+    }
+
+    /// Start the span of a call, in the current context of OpenTelemetry. It is noop if not enabled.
+    pub(crate) fn start_span(
+        &self,
+        name: &'static str,
+        attributes: &[(&'static str, &str)],
+    ) -> ClientSpan {
+        Python::attach(|py| {
+            let Some(tracer) = TRACER.get(py) else {
+                return ClientSpan(None);
+            };
+            let start = || -> PyResult<Py<PyAny>> {
+                let kwargs = PyDict::new(py);
+                kwargs.set_item(
+                    "kind",
+                    py.import("opentelemetry.trace")?
+                        .getattr("SpanKind")?
+                        .getattr("CLIENT")?,
+                )?;
+                kwargs.set_item(
+                    "attributes",
+                    attributes_dict(py, &self.namespace, attributes)?,
+                )?;
+                Ok(tracer
+                    .call_method(py, "start_span", (name,), Some(&kwargs))?
+                    .into_any())
+            };
+            ClientSpan(start().ok())
+        })
+    }
+
+    /// Spans of the calls of listener func, linked to the span of the call adding the listener,
+    /// since the push of server carries no context of OpenTelemetry.
+    pub(crate) fn listener_spans(
+        &self,
+        name: &'static str,
+        attributes: &[(&'static str, &str)],
+        span: &ClientSpan,
+    ) -> ListenerSpans {
+        ListenerSpans {
+            name,
+            namespace: self.namespace.clone(),
+            attributes: attributes
+                .iter()
+                .map(|(key, value)| (*key, value.to_string()))
+                .collect(),
+            link: span.span_context(),
+        }
+    }
+}
+
+/// Span of a call of client, which ends with the result of the call.
+pub(crate) struct ClientSpan(Option<Py<PyAny>>);
+
+impl ClientSpan {
+    /// Run the call in this span, e.g. of the sync clients.
+    pub(crate) fn in_scope<T>(&self, call: impl FnOnce() -> PyResult<T>) -> PyResult<T> {
+        let result = call();
+        self.end(&result);
+        result
+    }
+
+    /// Run the future in this span, e.g. of the async clients.
+    pub(crate) async fn instrument<T>(
+        self,
+        future: impl Future<Output = PyResult<T>>,
+    ) -> PyResult<T> {
+        let result = future.await;
+        self.end(&result);
+        result
+    }
+
+    fn span_context(&self) -> Option<Py<PyAny>> {
+        let span = self.0.as_ref()?;
+        Python::attach(|py| {
+            span.call_method0(py, "get_span_context")
+                .ok()
+                .map(Py::into_any)
+        })
+    }
+
+    fn end<T>(&self, result: &PyResult<T>) {
+        let Some(span) = self.0.as_ref() else {
+            return;
+        };
+        Python::attach(|py| {
+            if let Err(err) = result {
+                let _ = record_err(py, span.bind(py), err);
+            }
+            let _ = span.call_method0(py, "end");
+        });
+    }
+}
+
+fn record_err(py: Python<'_>, span: &Bound<'_, PyAny>, err: &PyErr) -> PyResult<()> {
+    span.call_method1("record_exception", (err.value(py),))?;
+    let status = py
+        .import("opentelemetry.trace")?
+        .getattr("Status")?
+        .call1((
+            py.import("opentelemetry.trace")?
+                .getattr("StatusCode")?
+                .getattr("ERROR")?,
+            err.value(py).str()?,
+        ))?;
+    span.call_method1("set_status", (status,))?;
+    Ok(())
+}
+
+/// Spans of the calls of a listener func, each in a new span linked to the call adding the listener.
+pub(crate) struct ListenerSpans {
+    name: &'static str,
+    namespace: String,
+    attributes: Vec<(&'static str, String)>,
+    link: Option<Py<PyAny>>,
+}
+
+impl ListenerSpans {
+    fn start_span(&self, py: Python<'_>, tracer: &Py<PyAny>) -> PyResult<Py<PyAny>> {
+        let trace = py.import("opentelemetry.trace")?;
+        let attributes: Vec<(&'static str, &str)> = self
+            .attributes
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .collect();
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("kind", trace.getattr("SpanKind")?.getattr("CONSUMER")?)?;
+        kwargs.set_item(
+            "attributes",
+            attributes_dict(py, &self.namespace, &attributes)?,
+        )?;
+        if let Some(link) = &self.link {
+            let links = PyList::new(py, [trace.getattr("Link")?.call1((link,))?])?;
+            kwargs.set_item("links", links)?;
+        }
+        // a new trace, not the context of the thread calling the listener
+        kwargs.set_item(
+            "context",
+            py.import("opentelemetry.context")?
+                .getattr("Context")?
+                .call0()?,
+        )?;
+        tracer.call_method(py, "start_span", (self.name,), Some(&kwargs))
+    }
+
+    /// Call the listener func in a span, as the current context, so that its spans are the children.
+    pub(crate) fn call<'py>(
+        &self,
+        py: Python<'py>,
+        func: &Py<PyAny>,
+        args: impl PyCallArgs<'py>,
+    ) -> PyResult<Py<PyAny>> {
+        let Some(span) = TRACER
+            .get(py)
+            .and_then(|tracer| self.start_span(py, tracer).ok())
+        else {
+            return func.call1(py, args);
+        };
+
+        let attach = || -> PyResult<(Bound<'py, PyAny>, Bound<'py, PyAny>)> {
+            let context = py.import("opentelemetry.context")?;
+            let token = context.call_method1(
+                "attach",
+                (py.import("opentelemetry.trace")?
+                    .call_method1("set_span_in_context", (&span,))?,),
+            )?;
+            Ok((context.into_any(), token))
+        };
+        let attached = attach().ok();
+        let result = func.call1(py, args);
+        if let Some((context, token)) = attached {
+            let _ = context.call_method1("detach", (token,));
+        }
+        if let Err(err) = &result {
+            let _ = record_err(py, span.bind(py), err);
+        }
+        let _ = span.call_method0(py, "end");
+        result
+    }
+}
@@ -0,0 +1,153 @@
+#![deny(clippy::all)]
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use nacos_sdk::api::naming::ServiceInstance;
+
+use crate::load_balancer::{is_candidate, pick_weighted_random};
+
+/// Consecutive failures to eject an instance, default 5
+const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+/// Ejection time of an instance, default 30s
+const DEFAULT_EJECTION_TIME_MS: u64 = 30_000;
+/// Max percent of instances could be ejected, default 50
+const DEFAULT_MAX_EJECTION_PERCENT: u32 = 50;
+
+struct InstanceHealth {
+    consecutive_failures: u32,
+    ejected_until: Option<Instant>,
+    /// When it was reported, or in the instances of a service last time
+    last_seen: Instant,
+}
+
+impl InstanceHealth {
+    fn new(now: Instant) -> Self {
+        Self {
+            consecutive_failures: 0,
+            ejected_until: None,
+            last_seen: now,
+        }
+    }
+}
+
+/// Client-side circuit-breaker of instances, by the success or failure reported by callers.
+/// The instance (ip:port) which exceeds the failure threshold is ejected temporarily.
+pub(crate) struct OutlierDetector {
+    failure_threshold: u32,
+    ejection_time: Duration,
+    max_ejection_percent: u32,
+    healths: Mutex<HashMap<String, InstanceHealth>>,
+}
+
+impl OutlierDetector {
+    pub(crate) fn new(client_options: &crate::ClientOptions) -> Self {
+        Self {
+            failure_threshold: client_options
+                .outlier_failure_threshold
+                .unwrap_or(DEFAULT_FAILURE_THRESHOLD)
+                .max(1),
+            ejection_time: Duration::from_millis(
+                client_options
+                    .outlier_ejection_time_ms
+                    .unwrap_or(DEFAULT_EJECTION_TIME_MS),
+            ),
+            max_ejection_percent: client_options
+                .outlier_max_ejection_percent
+                .unwrap_or(DEFAULT_MAX_EJECTION_PERCENT)
+                .min(100),
+            healths: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn report_success(&self, ip: &str, port: i32) {
+        let ip_and_port = instance_key(ip, port);
+        let mut healths = self.healths.lock().unwrap();
+        healths.remove(&ip_and_port);
+    }
+
+    pub(crate) fn report_failure(&self, ip: &str, port: i32) {
+        let ip_and_port = instance_key(ip, port);
+        let now = Instant::now();
+        let mut healths = self.healths.lock().unwrap();
+        let health = healths
+            .entry(ip_and_port)
+            .or_insert_with(|| InstanceHealth::new(now));
+        health.last_seen = now;
+        if health.ejected_until.is_some_and(|until| until > now) {
+            return;
+        }
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= self.failure_threshold {
+            health.consecutive_failures = 0;
+            health.ejected_until = Some(now + self.ejection_time);
+        }
+    }
+
+    /// The ip:port of instances are ejected now, at most max_ejection_percent of the instances,
+    /// rounded up so that one could be ejected among a few instances.
+    pub(crate) fn ejected_among<'a, I>(&self, instances: I) -> HashSet<String>
+    where
+        I: IntoIterator<Item = &'a ServiceInstance>,
+    {
+        let now = Instant::now();
+        let mut healths = self.healths.lock().unwrap();
+
+        let mut current = HashSet::new();
+        let mut ejected = Vec::new();
+        for instance in instances {
+            let ip_and_port = instance_key(&instance.ip, instance.port);
+            if let Some(health) = healths.get_mut(&ip_and_port) {
+                health.last_seen = now;
+                if let Some(until) = health.ejected_until
+                    && until > now
+                {
+                    ejected.push((until, ip_and_port.clone()));
+                }
+            }
+            current.insert(ip_and_port);
+        }
+        // the instances are of several services, so only the ones not in this service, not ejected,
+        // and not seen in any service for the ejection time are removed, e.g. deregistered
+        healths.retain(|ip_and_port, health| {
+            current.contains(ip_and_port)
+                || health.ejected_until.is_some_and(|until| until > now)
+                || now.duration_since(health.last_seen) < self.ejection_time
+        });
+
+        // keep the latest ejected, others are released early
+        let max_ejection = (current.len() * self.max_ejection_percent as usize).div_ceil(100);
+        ejected.sort_unstable_by_key(|(until, _)| std::cmp::Reverse(*until));
+        ejected.truncate(max_ejection);
+        ejected
+            .into_iter()
+            .map(|(_, ip_and_port)| ip_and_port)
+            .collect()
+    }
+
+    /// Remove the ejected instances, see `ejected_among`.
+    pub(crate) fn filter(&self, mut instances: Vec<ServiceInstance>) -> Vec<ServiceInstance> {
+        let ejected = self.ejected_among(&instances);
+        if !ejected.is_empty() {
+            instances
+                .retain(|instance| !ejected.contains(&instance_key(&instance.ip, instance.port)));
+        }
+        instances
+    }
+
+    /// Pick one healthy instance by weighted random, except the ejected instances.
+    pub(crate) fn select_one(&self, instances: Vec<ServiceInstance>) -> Option<ServiceInstance> {
+        let instances = self.filter(instances.into_iter().filter(is_candidate).collect());
+        if instances.is_empty() {
+            return None;
+        }
+        let candidates: Vec<&ServiceInstance> = instances.iter().collect();
+        Some(pick_weighted_random(&candidates).clone())
+    }
+}
+
+/// The key of instance health, ip:port
+pub(crate) fn instance_key(ip: &str, port: i32) -> String {
+    format!("{}:{}", ip, port)
+}
@@ -0,0 +1,811 @@
+#![deny(clippy::all)]
+
+use crate::future_into_py;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::types::{PyAnyMethods, PyCFunction, PyDict, PyTuple};
+use pyo3::{
+    Bound, IntoPyObject, IntoPyObjectExt, PyAny, PyErr, PyResult, Python, pyclass, pymethods,
+};
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use tokio::sync::Mutex as AsyncMutex;
+
+use nacos_sdk::api::naming::{NamingService, ServiceInstance};
+
+use crate::connection::ConnectionTracker;
+use crate::health_check::{FailureAction, HealthCheckers, registration_key};
+use crate::naming::{NacosServiceInstance, transfer_rust_instance_to_ffi};
+
+/// The registrations of the clients alive with the process they are built in, deregistered by one
+/// hook of atexit. The ones inherited by fork are left to the parent process.
+static LIVE: Mutex<Vec<(u32, Weak<Registrations>)>> = Mutex::new(Vec::new());
+static ATEXIT_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// (service_name, group)
+type ServiceKey = (String, String);
+
+#[derive(Clone)]
+struct Registration {
+    service_name: String,
+    group: String,
+    instance: ServiceInstance,
+}
+
+/// The instances registered by a naming client, which are reconciled with server periodically
+/// when `registration_reconcile_interval_ms` is set, and deregistered on client close or interpreter exit.
+///
+/// The server keeps one publish of a service per client: a register or batch register replaces the
+/// last one, and a deregister removes it whatever the instance is. So the owned instances of a service
+/// are always published together, see `publish`.
+pub(crate) struct Registrations {
+    naming_service: NamingService,
+    health_checkers: Arc<HealthCheckers>,
+    connection: Arc<ConnectionTracker>,
+    registrations: Mutex<HashMap<String, Registration>>,
+    /// Held across read, publish and write of the owned instances of a (service, group),
+    /// since every publish replaces the last one of the service in server.
+    service_locks: Mutex<HashMap<ServiceKey, Arc<AsyncMutex<()>>>>,
+    closed: AtomicBool,
+}
+
+impl Registrations {
+    pub(crate) fn new(
+        naming_service: NamingService,
+        health_checkers: Arc<HealthCheckers>,
+        connection: Arc<ConnectionTracker>,
+        reconcile_interval_ms: Option<u64>,
+    ) -> PyResult<Arc<Self>> {
+        let registrations = Arc::new(Self {
+            naming_service,
+            health_checkers,
+            connection,
+            registrations: Mutex::new(HashMap::new()),
+            service_locks: Mutex::new(HashMap::new()),
+            closed: AtomicBool::new(false),
+        });
+
+        if let Some(interval_ms) = reconcile_interval_ms.filter(|interval_ms| *interval_ms > 0) {
+            Self::spawn_reconciler(Arc::downgrade(&registrations), interval_ms);
+        }
+        Python::attach(register_atexit)?;
+        let mut live = LIVE.lock().unwrap();
+        live.retain(|(_, registrations)| registrations.strong_count() > 0);
+        live.push((std::process::id(), Arc::downgrade(&registrations)));
+        Ok(registrations)
+    }
+
+    /// Reconcile on its own thread, after reconnects or server restarts the missing instances
+    /// are registered again. It ends when the client is dropped or closed.
+    fn spawn_reconciler(registrations: Weak<Self>, interval_ms: u64) {
+        let _ = std::thread::Builder::new()
+            .name("nacos-registration-reconciler".to_string())
+            .spawn(move || {
+                loop {
+                    std::thread::sleep(Duration::from_millis(interval_ms));
+                    let Some(registrations) = registrations.upgrade() else {
+                        return;
+                    };
+                    if registrations.closed.load(Ordering::Acquire) {
+                        return;
+                    }
+                    let _ = crate::block_on(registrations.reconcile());
+                }
+            });
+    }
+
+    /// The lock of the owned instances of the service.
+    fn service_lock(&self, service_name: &str, group: &str) -> Arc<AsyncMutex<()>> {
+        self.service_locks
+            .lock()
+            .unwrap()
+            .entry((service_name.to_string(), group.to_string()))
+            .or_default()
+            .clone()
+    }
+
+    /// The owned instances of the service with their keys of `registration_key`, sorted by key.
+    fn owned(&self, service_name: &str, group: &str) -> Vec<(String, ServiceInstance)> {
+        let mut owned: Vec<(String, ServiceInstance)> = self
+            .registrations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, r)| r.service_name == service_name && r.group == group)
+            .map(|(key, r)| (key.clone(), r.instance.clone()))
+            .collect();
+        owned.sort_by(|(a, _), (b, _)| a.cmp(b));
+        owned
+    }
+
+    /// Replace the owned instances of the service.
+    fn set_owned(&self, service_name: &str, group: &str, owned: Vec<(String, ServiceInstance)>) {
+        let mut registrations = self.registrations.lock().unwrap();
+        registrations.retain(|_, r| r.service_name != service_name || r.group != group);
+        for (key, instance) in owned {
+            registrations.insert(
+                key,
+                Registration {
+                    service_name: service_name.to_string(),
+                    group: group.to_string(),
+                    instance,
+                },
+            );
+        }
+    }
+
+    /// The owned instances as published, the failing ones of health check are unhealthy or left out
+    /// by its failure action.
+    fn published(&self, owned: &[(String, ServiceInstance)]) -> Vec<(String, ServiceInstance)> {
+        owned
+            .iter()
+            .filter_map(
+                |(key, instance)| match self.health_checkers.failure_action(key) {
+                    None => Some((key.clone(), instance.clone())),
+                    Some(FailureAction::Unhealthy) => {
+                        let mut instance = instance.clone();
+                        instance.healthy = false;
+                        Some((key.clone(), instance))
+                    }
+                    Some(FailureAction::Deregister) => None,
+                },
+            )
+            .collect()
+    }
+
+    /// Publish the owned instances of the service together, one by register and more by batch register.
+    /// When none is published, the service is deregistered by `deregistered` if any.
+    /// The requests are observed as the public method calling it.
+    async fn publish(
+        &self,
+        method: &'static str,
+        service_name: &str,
+        group: &str,
+        owned: &[(String, ServiceInstance)],
+        deregistered: Option<ServiceInstance>,
+    ) -> nacos_sdk::api::error::Result<()> {
+        let mut instances: Vec<ServiceInstance> = self
+            .published(owned)
+            .into_iter()
+            .map(|(_, instance)| instance)
+            .collect();
+        match instances.len() {
+            0 => match deregistered {
+                Some(instance) => {
+                    self.connection
+                        .track(
+                            method,
+                            self.naming_service.deregister_instance(
+                                service_name.to_string(),
+                                Some(group.to_string()),
+                                instance,
+                            ),
+                        )
+                        .await
+                }
+                None => Ok(()),
+            },
+            1 => {
+                self.connection
+                    .track(
+                        method,
+                        self.naming_service.register_instance(
+                            service_name.to_string(),
+                            Some(group.to_string()),
+                            instances.remove(0),
+                        ),
+                    )
+                    .await
+            }
+            _ => {
+                self.connection
+                    .track(
+                        method,
+                        self.naming_service.batch_register_instance(
+                            service_name.to_string(),
+                            Some(group.to_string()),
+                            instances,
+                        ),
+                    )
+                    .await
+            }
+        }
+    }
+
+    /// Register the instance along with the other owned instances of the service.
+    pub(crate) async fn register(
+        &self,
+        service_name: &str,
+        group: &str,
+        instance: ServiceInstance,
+    ) -> PyResult<()> {
+        let key = registration_key(service_name, group, &instance.ip, instance.port);
+        let lock = self.service_lock(service_name, group);
+        let _guard = lock.lock().await;
+        let mut owned = self.owned(service_name, group);
+        owned.retain(|(owned_key, _)| *owned_key != key);
+        owned.push((key, instance));
+        self.publish("register_instance", service_name, group, &owned, None)
+            .await
+            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        self.set_owned(service_name, group, owned);
+        Ok(())
+    }
+
+    /// Batch register replaces all the owned instances of the service.
+    pub(crate) async fn batch_register(
+        &self,
+        service_name: &str,
+        group: &str,
+        instances: Vec<ServiceInstance>,
+    ) -> PyResult<()> {
+        let lock = self.service_lock(service_name, group);
+        let _guard = lock.lock().await;
+        let previous = self.owned(service_name, group);
+        let owned: Vec<(String, ServiceInstance)> = instances
+            .into_iter()
+            .map(|instance| {
+                (
+                    registration_key(service_name, group, &instance.ip, instance.port),
+                    instance,
+                )
+            })
+            .collect();
+        let deregistered = previous.into_iter().next().map(|(_, instance)| instance);
+        self.publish(
+            "batch_register_instance",
+            service_name,
+            group,
+            &owned,
+            deregistered,
+        )
+        .await
+        .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        self.set_owned(service_name, group, owned);
+        Ok(())
+    }
+
+    /// Deregister the instances, the other owned instances of the service are published again.
+    pub(crate) async fn deregister(
+        &self,
+        method: &'static str,
+        service_name: &str,
+        group: &str,
+        instances: &[ServiceInstance],
+    ) -> nacos_sdk::api::error::Result<()> {
+        let keys: HashSet<String> = instances
+            .iter()
+            .map(|instance| registration_key(service_name, group, &instance.ip, instance.port))
+            .collect();
+        for instance in instances {
+            self.health_checkers
+                .stop(service_name, group, &instance.ip, instance.port);
+        }
+        let lock = self.service_lock(service_name, group);
+        let _guard = lock.lock().await;
+        let (removed, remaining): (Vec<_>, Vec<_>) = self
+            .owned(service_name, group)
+            .into_iter()
+            .partition(|(key, _)| keys.contains(key));
+        let deregistered = removed
+            .into_iter()
+            .map(|(_, instance)| instance)
+            .chain(instances.iter().cloned())
+            .next();
+        self.publish(method, service_name, group, &remaining, deregistered)
+            .await?;
+        self.set_owned(service_name, group, remaining);
+        Ok(())
+    }
+
+    /// The health of the owned instance is changed, publish the instances of the service again.
+    pub(crate) async fn republish(
+        &self,
+        service_name: &str,
+        group: &str,
+        key: &str,
+    ) -> nacos_sdk::api::error::Result<()> {
+        let lock = self.service_lock(service_name, group);
+        let _guard = lock.lock().await;
+        let owned = self.owned(service_name, group);
+        let deregistered = owned
+            .iter()
+            .find(|(owned_key, _)| owned_key == key)
+            .map(|(_, instance)| instance.clone());
+        self.publish("health_check", service_name, group, &owned, deregistered)
+            .await
+    }
+
+    fn snapshot(&self) -> Vec<(String, Registration)> {
+        self.registrations
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, r)| (key.clone(), r.clone()))
+            .collect()
+    }
+
+    /// Publish again the services of which owned instances are missing in server, returns the count of them.
+    pub(crate) async fn reconcile(&self) -> PyResult<usize> {
+        let services: HashSet<(String, String)> = self
+            .snapshot()
+            .into_iter()
+            .map(|(_, r)| (r.service_name, r.group))
+            .collect();
+
+        let mut reconciled = 0;
+        let mut first_err = None;
+        for (service_name, group) in services {
+            let lock = self.service_lock(&service_name, &group);
+            let _guard = lock.lock().await;
+            let owned = self.owned(&service_name, &group);
+            // the deregistered by the failing health check on purpose are not expected
+            let expected = self.published(&owned);
+            if expected.is_empty() {
+                continue;
+            }
+            let server_instances = match self
+                .connection
+                .track(
+                    "reconcile",
+                    self.naming_service.get_all_instances(
+                        service_name.clone(),
+                        Some(group.clone()),
+                        Vec::new(),
+                        false,
+                    ),
+                )
+                .await
+            {
+                Ok(instances) => instances,
+                Err(nacos_err) => {
+                    first_err.get_or_insert(nacos_err);
+                    continue;
+                }
+            };
+            let existing: HashSet<String> = server_instances
+                .iter()
+                .map(|i| registration_key(&service_name, &group, &i.ip, i.port))
+                .collect();
+            let missing = expected
+                .iter()
+                .filter(|(key, _)| !existing.contains(key))
+                .count();
+            if missing == 0 {
+                continue;
+            }
+            match self
+                .publish("reconcile", &service_name, &group, &owned, None)
+                .await
+            {
+                Ok(()) => reconciled += missing,
+                Err(nacos_err) => {
+                    first_err.get_or_insert(nacos_err);
+                }
+            }
+        }
+
+        match first_err {
+            Some(nacos_err) => Err(PyRuntimeError::new_err(format!("{:?}", &nacos_err))),
+            None => Ok(reconciled),
+        }
+    }
+
+    /// Re-register the owned instance with the changed fields, observed as method.
+    pub(crate) async fn update<F>(
+        &self,
+        method: &'static str,
+        service_name: String,
+        group: String,
+        ip: String,
+        port: i32,
+        change: F,
+    ) -> PyResult<()>
+    where
+        F: FnOnce(&mut ServiceInstance),
+    {
+        let key = registration_key(&service_name, &group, &ip, port);
+        let lock = self.service_lock(&service_name, &group);
+        let _guard = lock.lock().await;
+        let mut owned = self.owned(&service_name, &group);
+        let Some((_, instance)) = owned.iter_mut().find(|(owned_key, _)| *owned_key == key) else {
+            return Err(not_owned(&service_name, &group, &ip, port));
+        };
+        change(instance);
+
+        self.publish(method, &service_name, &group, &owned, None)
+            .await
+            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        self.set_owned(&service_name, &group, owned);
+        Ok(())
+    }
+
+    /// Update the instance owned by this client and existing in server in place, by re-register
+    /// without deregister, so that the consumers see no flap.
+    pub(crate) async fn update_instance(
+        &self,
+        service_name: String,
+        group: String,
+        instance: ServiceInstance,
+    ) -> PyResult<()> {
+        let key = registration_key(&service_name, &group, &instance.ip, instance.port);
+        if !self
+            .owned(&service_name, &group)
+            .iter()
+            .any(|(owned_key, _)| *owned_key == key)
+        {
+            return Err(not_owned(
+                &service_name,
+                &group,
+                &instance.ip,
+                instance.port,
+            ));
+        }
+        let server_instances = self
+            .connection
+            .track(
+                "update_instance",
+                self.naming_service.get_all_instances(
+                    service_name.clone(),
+                    Some(group.clone()),
+                    Vec::new(),
+                    false,
+                ),
+            )
+            .await
+            .map_err(|nacos_err| PyRuntimeError::new_err(format!("{:?}", &nacos_err)))?;
+        if !server_instances
+            .iter()
+            .any(|i| i.ip == instance.ip && i.port == instance.port)
+        {
+            return Err(PyErr::new::<PyValueError, _>(format!(
+                "Instance {}:{} of service `{}` in group `{}` does not exist",
+                instance.ip, instance.port, service_name, group
+            )));
+        }
+
+        let (ip, port) = (instance.ip.clone(), instance.port);
+        self.update("update_instance", service_name, group, ip, port, |owned| {
+            *owned = instance
+        })
+        .await
+    }
+
+    /// Register the instances of several services, the ones of a service are merged into its owned
+    /// instances and published together, continue on error of a service.
+    /// The result of each is None on success, otherwise the err of its service.
+    pub(crate) async fn register_many(
+        &self,
+        registrations: Vec<(String, String, ServiceInstance)>,
+    ) -> Vec<Option<PyErr>> {
+        let mut services: Vec<(String, String, Vec<usize>)> = Vec::new();
+        for (index, (service_name, group, _)) in registrations.iter().enumerate() {
+            match services
+                .iter_mut()
+                .find(|(s, g, _)| s == service_name && g == group)
+            {
+                Some((_, _, indexes)) => indexes.push(index),
+                None => services.push((service_name.clone(), group.clone(), vec![index])),
+            }
+        }
+
+        let mut results: Vec<Option<PyErr>> = registrations.iter().map(|_| None).collect();
+        for (service_name, group, indexes) in services {
+            let lock = self.service_lock(&service_name, &group);
+            let _guard = lock.lock().await;
+            let mut owned = self.owned(&service_name, &group);
+            for &index in &indexes {
+                let instance = registrations[index].2.clone();
+                let key = registration_key(&service_name, &group, &instance.ip, instance.port);
+                owned.retain(|(owned_key, _)| *owned_key != key);
+                owned.push((key, instance));
+            }
+            match self
+                .publish("register_many", &service_name, &group, &owned, None)
+                .await
+            {
+                Ok(()) => self.set_owned(&service_name, &group, owned),
+                Err(nacos_err) => {
+                    for index in indexes {
+                        results[index] = Some(PyRuntimeError::new_err(format!("{:?}", &nacos_err)));
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    /// Deregister the owned ones of the instances together, the remaining owned instances are
+    /// published by one request. The result of each is None on success, otherwise the err,
+    /// which is ValueError for the instance not registered by this client.
+    pub(crate) async fn deregister_many(
+        &self,
+        service_name: String,
+        group: String,
+        instances: Vec<ServiceInstance>,
+    ) -> Vec<Option<PyErr>> {
+        let owned: HashSet<String> = self
+            .owned(&service_name, &group)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        let is_owned: Vec<bool> = instances
+            .iter()
+            .map(|instance| {
+                owned.contains(&registration_key(
+                    &service_name,
+                    &group,
+                    &instance.ip,
+                    instance.port,
+                ))
+            })
+            .collect();
+        let deregistered: Vec<ServiceInstance> = instances
+            .iter()
+            .zip(&is_owned)
+            .filter(|(_, is_owned)| **is_owned)
+            .map(|(instance, _)| instance.clone())
+            .collect();
+        let result = if deregistered.is_empty() {
+            Ok(())
+        } else {
+            self.deregister(
+                "batch_deregister_instance",
+                &service_name,
+                &group,
+                &deregistered,
+            )
+            .await
+        };
+
+        instances
+            .iter()
+            .zip(is_owned)
+            .map(|(instance, is_owned)| {
+                if !is_owned {
+                    return Some(not_owned(
+                        &service_name,
+                        &group,
+                        &instance.ip,
+                        instance.port,
+                    ));
+                }
+                result
+                    .as_ref()
+                    .err()
+                    .map(|nacos_err| PyRuntimeError::new_err(format!("{:?}", nacos_err)))
+            })
+            .collect()
+    }
+
+    /// Deregister all the owned instances, continue on error and return the first one.
+    pub(crate) async fn deregister_all(&self) -> PyResult<()> {
+        self.health_checkers.stop_all();
+        let services: HashSet<(String, String)> = self
+            .snapshot()
+            .into_iter()
+            .map(|(_, r)| (r.service_name, r.group))
+            .collect();
+
+        let mut first_err = None;
+        for (service_name, group) in services {
+            let instances: Vec<ServiceInstance> = self
+                .owned(&service_name, &group)
+                .into_iter()
+                .map(|(_, instance)| instance)
+                .collect();
+            if let Err(nacos_err) = self
+                .deregister("deregister_all", &service_name, &group, &instances)
+                .await
+            {
+                first_err.get_or_insert(nacos_err);
+            }
+        }
+
+        match first_err {
+            Some(nacos_err) => Err(PyRuntimeError::new_err(format!("{:?}", &nacos_err))),
+            None => Ok(()),
+        }
+    }
+
+    /// Stop reconciling and deregister all, only the first call takes effect.
+    pub(crate) async fn close(&self) -> PyResult<()> {
+        if self.closed.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        let result = self.deregister_all().await;
+        self.connection.shutdown();
+        result
+    }
+}
+
+fn not_owned(service_name: &str, group: &str, ip: &str, port: i32) -> PyErr {
+    PyErr::new::<PyValueError, _>(format!(
+        "Instance {}:{} of service `{}` in group `{}` is not registered by this client",
+        ip, port, service_name, group
+    ))
+}
+
+/// Register the hook of atexit deregistering the instances of all the clients, only once in the process.
+fn register_atexit(py: Python<'_>) -> PyResult<()> {
+    if ATEXIT_REGISTERED.swap(true, Ordering::AcqRel) {
+        return Ok(());
+    }
+    let on_exit = PyCFunction::new_closure(
+        py,
+        Some(c"nacos_deregister_all"),
+        None,
+        |args: &Bound<'_, PyTuple>, _kwargs: Option<&Bound<'_, PyDict>>| {
+            let pid = std::process::id();
+            let live: Vec<Arc<Registrations>> = LIVE
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(built_in, _)| *built_in == pid)
+                .filter_map(|(_, registrations)| registrations.upgrade())
+                .collect();
+            for registrations in live {
+                let _ = args.py().detach(|| crate::block_on(registrations.close()));
+            }
+        },
+    );
+    let result = on_exit.and_then(|on_exit| {
+        py.import("atexit")?.call_method1("register", (on_exit,))?;
+        Ok(())
+    });
+    if result.is_err() {
+        ATEXIT_REGISTERED.store(false, Ordering::Release);
+    }
+    result
+}
+
+/// Manager of the instances registered through a naming client, it remembers every instance
+/// and reconciles them with server after reconnects or server restarts, periodically when
+/// `registration_reconcile_interval_ms` is set, otherwise by `reconcile`.
+/// Get by `NacosNamingClient.registration_manager` or `AsyncNacosNamingClient.registration_manager`,
+/// the methods of the latter return awaitable.
+#[pyclass(module = "nacos_sdk_rust_binding_py")]
+pub struct RegistrationManager {
+    inner: Arc<Registrations>,
+    built_in: crate::fork::BuiltIn,
+    is_async: bool,
+}
+
+impl RegistrationManager {
+    pub(crate) fn new(
+        inner: Arc<Registrations>,
+        built_in: crate::fork::BuiltIn,
+        is_async: bool,
+    ) -> Self {
+        Self {
+            inner,
+            built_in,
+            is_async,
+        }
+    }
+
+    /// Block on the future, or return an awaitable of it for async client.
+    fn run<'p, F, T>(&self, py: Python<'p>, future: F) -> PyResult<Bound<'p, PyAny>>
+    where
+        F: Future<Output = PyResult<T>> + Send + 'static,
+        T: for<'py> IntoPyObject<'py> + Send + 'static,
+    {
+        self.built_in.check()?;
+        if self.is_async {
+            future_into_py(py, future)
+        } else {
+            py.detach(|| crate::block_on(future))?.into_bound_py_any(py)
+        }
+    }
+}
+
+#[pymethods]
+impl RegistrationManager {
+    /// All the registered instances, as tuple (service_name, group, NacosServiceInstance).
+    pub fn instances(&self) -> Vec<(String, String, NacosServiceInstance)> {
+        self.inner
+            .snapshot()
+            .into_iter()
+            .map(|(_, r)| {
+                (
+                    r.service_name,
+                    r.group,
+                    transfer_rust_instance_to_ffi(&r.instance),
+                )
+            })
+            .collect()
+    }
+
+    /// Register again the instances which are missing in server, returns the count of them.
+    /// If it fails, pay attention to err
+    pub fn reconcile<'p>(&self, py: Python<'p>) -> PyResult<Bound<'p, PyAny>> {
+        let inner = self.inner.clone();
+        self.run(py, async move { inner.reconcile().await })
+    }
+
+    /// Merge the metadata into the registered instance, and re-register it.
+    /// If it fails, pay attention to err
+    pub fn update_metadata<'p>(
+        &self,
+        py: Python<'p>,
+        service_name: String,
+        group: String,
+        service_instance: NacosServiceInstance,
+        metadata: HashMap<String, String>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        let inner = self.inner.clone();
+        self.run(py, async move {
+            inner
+                .update(
+                    "update_metadata",
+                    service_name,
+                    group,
+                    service_instance.ip,
+                    service_instance.port,
+                    |instance| instance.metadata.extend(metadata),
+                )
+                .await
+        })
+    }
+
+    /// Set the weight of the registered instance, and re-register it.
+    /// If it fails, pay attention to err
+    pub fn set_weight<'p>(
+        &self,
+        py: Python<'p>,
+        service_name: String,
+        group: String,
+        service_instance: NacosServiceInstance,
+        weight: f64,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        let inner = self.inner.clone();
+        self.run(py, async move {
+            inner
+                .update(
+                    "set_weight",
+                    service_name,
+                    group,
+                    service_instance.ip,
+                    service_instance.port,
+                    |instance| instance.weight = weight,
+                )
+                .await
+        })
+    }
+
+    /// Set the registered instance enabled or not, and re-register it.
+    /// If it fails, pay attention to err
+    pub fn set_enabled<'p>(
+        &self,
+        py: Python<'p>,
+        service_name: String,
+        group: String,
+        service_instance: NacosServiceInstance,
+        enabled: bool,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        let inner = self.inner.clone();
+        self.run(py, async move {
+            inner
+                .update(
+                    "set_enabled",
+                    service_name,
+                    group,
+                    service_instance.ip,
+                    service_instance.port,
+                    |instance| instance.enabled = enabled,
+                )
+                .await
+        })
+    }
+
+    /// Deregister all the registered instances.
+    /// If it fails, pay attention to err
+    pub fn deregister_all<'p>(&self, py: Python<'p>) -> PyResult<Bound<'p, PyAny>> {
+        let inner = self.inner.clone();
+        self.run(py, async move { inner.deregister_all().await })
+    }
+}
@@ -0,0 +1,167 @@
+#![deny(clippy::all)]
+
+use pyo3::exceptions::PyValueError;
+use pyo3::types::{PyAnyMethods, PyDict, PyDictMethods};
+use pyo3::{Bound, PyAny, PyErr, PyResult};
+
+use std::collections::HashMap;
+
+use nacos_sdk::api::naming::ServiceInstance;
+
+/// A requirement on the metadata of instance.
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Requirement {
+    /// key=value or key==value
+    Equals(String, String),
+    /// key!=value, it matches when the key is absent
+    NotEquals(String, String),
+    /// key in (v1,v2)
+    In(String, Vec<String>),
+    /// key notin (v1,v2), it matches when the key is absent
+    NotIn(String, Vec<String>),
+    /// key
+    Exists(String),
+    /// !key
+    NotExists(String),
+}
+
+impl Requirement {
+    fn matches(&self, metadata: &HashMap<String, String>) -> bool {
+        match self {
+            Requirement::Equals(key, value) => metadata.get(key) == Some(value),
+            Requirement::NotEquals(key, value) => metadata.get(key) != Some(value),
+            Requirement::In(key, values) => metadata.get(key).is_some_and(|v| values.contains(v)),
+            Requirement::NotIn(key, values) => {
+                !metadata.get(key).is_some_and(|v| values.contains(v))
+            }
+            Requirement::Exists(key) => metadata.contains_key(key),
+            Requirement::NotExists(key) => !metadata.contains_key(key),
+        }
+    }
+}
+
+/// Selector of instances by metadata, all the requirements must match.
+/// It is a dict of key-value, or a label-selector expression, e.g. `version in (1,2),zone!=b`,
+/// which supports `k=v`, `k==v`, `k!=v`, `k in (v1,v2)`, `k notin (v1,v2)`, `k` and `!k`.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub(crate) struct MetadataSelector {
+    requirements: Vec<Requirement>,
+}
+
+impl MetadataSelector {
+    /// Build by the arg `metadata_selector`, it matches all without the arg.
+    pub(crate) fn parse(selector: Option<Bound<'_, PyAny>>) -> PyResult<Self> {
+        let Some(selector) = selector else {
+            return Ok(Self::default());
+        };
+        if let Ok(dict) = selector.cast::<PyDict>() {
+            let mut requirements = Vec::with_capacity(dict.len());
+            for (key, value) in dict.iter() {
+                requirements.push(Requirement::Equals(key.extract()?, value.extract()?));
+            }
+            return Ok(Self { requirements });
+        }
+        if let Ok(expression) = selector.extract::<String>() {
+            return Self::parse_expression(&expression);
+        }
+        Err(PyErr::new::<PyValueError, _>(
+            "Arg `metadata_selector` must be a dict or a str",
+        ))
+    }
+
+    fn parse_expression(expression: &str) -> PyResult<Self> {
+        let invalid = |reason: &str| {
+            PyErr::new::<PyValueError, _>(format!(
+                "Invalid metadata_selector `{}`, {}",
+                expression, reason
+            ))
+        };
+
+        let mut requirements = Vec::new();
+        for term in split_terms(expression).map_err(invalid)? {
+            let term = term.trim();
+            if term.is_empty() {
+                continue;
+            }
+            let requirement = if let Some(open) = term.find('(') {
+                if !term.ends_with(')') {
+                    return Err(invalid("missing `)`"));
+                }
+                let values: Vec<String> = term[open + 1..term.len() - 1]
+                    .split(',')
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+                    .collect();
+                let mut words = term[..open].split_whitespace();
+                match (words.next(), words.next(), words.next()) {
+                    (Some(key), Some("in"), None) => Requirement::In(key.to_string(), values),
+                    (Some(key), Some("notin"), None) => Requirement::NotIn(key.to_string(), values),
+                    _ => return Err(invalid("the set operator must be `in` or `notin`")),
+                }
+            } else if let Some((key, value)) = term.split_once("!=") {
+                Requirement::NotEquals(key.trim().to_string(), value.trim().to_string())
+            } else if let Some((key, value)) = term.split_once("==") {
+                Requirement::Equals(key.trim().to_string(), value.trim().to_string())
+            } else if let Some((key, value)) = term.split_once('=') {
+                Requirement::Equals(key.trim().to_string(), value.trim().to_string())
+            } else if let Some(key) = term.strip_prefix('!') {
+                Requirement::NotExists(key.trim().to_string())
+            } else {
+                Requirement::Exists(term.to_string())
+            };
+
+            let key = match &requirement {
+                Requirement::Equals(key, _)
+                | Requirement::NotEquals(key, _)
+                | Requirement::In(key, _)
+                | Requirement::NotIn(key, _)
+                | Requirement::Exists(key)
+                | Requirement::NotExists(key) => key,
+            };
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(invalid("the key must not be empty or contain whitespace"));
+            }
+            requirements.push(requirement);
+        }
+        Ok(Self { requirements })
+    }
+
+    fn matches(&self, instance: &ServiceInstance) -> bool {
+        self.requirements
+            .iter()
+            .all(|requirement| requirement.matches(&instance.metadata))
+    }
+
+    /// Remove the instances which do not match.
+    pub(crate) fn filter(&self, mut instances: Vec<ServiceInstance>) -> Vec<ServiceInstance> {
+        if !self.requirements.is_empty() {
+            instances.retain(|instance| self.matches(instance));
+        }
+        instances
+    }
+}
+
+/// Split by the commas which are not in parentheses.
+fn split_terms(expression: &str) -> Result<Vec<&str>, &'static str> {
+    let mut terms = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in expression.char_indices() {
+        match c {
+            '(' if depth == 0 => depth = 1,
+            '(' => return Err("nested `(`"),
+            ')' if depth == 1 => depth = 0,
+            ')' => return Err("unexpected `)`"),
+            ',' if depth == 0 => {
+                terms.push(&expression[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err("missing `)`");
+    }
+    terms.push(&expression[start..]);
+    Ok(terms)
+}
@@ -9,10 +9,10 @@ source venv/bin/activate
 
 # Install maturin:
 pip install maturin
-pip install behave
 
 # Build bindings:
 maturin develop
 
-# Run some tests:
-behave tests
+# Run the tests, the ones needing a Nacos server (env NACOS_SERVER_ADDR, default 127.0.0.1:8848)
+# are skipped when it is unreachable:
+python -m unittest discover -s tests -p "test_*.py" -v
@@ -0,0 +1,85 @@
+"""
+Tests of publish_config_bytes and get_config_bytes round trip through a real Nacos server.
+
+They need a Nacos server, set by env `NACOS_SERVER_ADDR`, default 127.0.0.1:8848,
+it is skipped when the server is unreachable. Run after `maturin develop`:
+
+    python -m unittest tests/test_config_bytes.py
+"""
+
+import asyncio
+import os
+import socket
+import unittest
+import uuid
+
+import nacos_sdk_rust_binding_py as nacos
+
+SERVER_ADDR = os.environ.get("NACOS_SERVER_ADDR", "127.0.0.1:8848")
+USERNAME = os.environ.get("NACOS_USERNAME", "nacos")
+PASSWORD = os.environ.get("NACOS_PASSWORD", "nacos")
+GROUP = "DEFAULT_GROUP"
+
+BLOBS = [
+    b"",
+    b"\x00",
+    b"\xff\xfe\xfd",
+    bytes(range(256)),
+    os.urandom(4096),
+    "héllo".encode("utf-8"),
+]
+
+
+def server_reachable() -> bool:
+    host, _, port = SERVER_ADDR.split(",")[0].rpartition(":")
+    try:
+        with socket.create_connection((host, int(port)), timeout=1):
+            return True
+    except OSError:
+        return False
+
+
+def options() -> nacos.ClientOptions:
+    return nacos.ClientOptions(SERVER_ADDR, "", "test_config_bytes", USERNAME, PASSWORD)
+
+
+@unittest.skipUnless(server_reachable(), "Nacos server %s is unreachable" % SERVER_ADDR)
+class ConfigBytesTest(unittest.TestCase):
+    def test_round_trip(self):
+        client = nacos.NacosConfigClient(options())
+        for blob in BLOBS:
+            data_id = "test-config-bytes-%s" % uuid.uuid4().hex
+            try:
+                self.assertTrue(client.publish_config_bytes(data_id, GROUP, blob))
+                self.assertEqual(client.get_config_bytes(data_id, GROUP), blob)
+                config_resp = client.get_config_resp(data_id, GROUP)
+                self.assertEqual(config_resp.content_type, "base64")
+                self.assertEqual(config_resp.content_bytes, blob)
+            finally:
+                client.remove_config(data_id, GROUP)
+
+    def test_round_trip_async(self):
+        async def round_trip(data_id: str, blob: bytes) -> bytes:
+            client = nacos.AsyncNacosConfigClient(options())
+            try:
+                await client.publish_config_bytes(data_id, GROUP, blob)
+                return await client.get_config_bytes(data_id, GROUP)
+            finally:
+                await client.remove_config(data_id, GROUP)
+
+        for blob in BLOBS:
+            data_id = "test-config-bytes-%s" % uuid.uuid4().hex
+            self.assertEqual(asyncio.run(round_trip(data_id, blob)), blob)
+
+    def test_text_config(self):
+        data_id = "test-config-bytes-%s" % uuid.uuid4().hex
+        client = nacos.NacosConfigClient(options())
+        try:
+            client.publish_config(data_id, GROUP, "plain text")
+            self.assertEqual(client.get_config_bytes(data_id, GROUP), b"plain text")
+        finally:
+            client.remove_config(data_id, GROUP)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,105 @@
+"""
+Tests of NacosServiceInstanceList, the instance list returned by get_all_instances / select_instances
+and passed to the naming listener, with a benchmark against converting all the instances.
+
+They need no Nacos server. Run after `maturin develop` (`--release` for the benchmark numbers):
+
+    python -m unittest -v tests/test_instance_list.py
+"""
+
+import pickle
+import timeit
+import unittest
+
+import nacos_sdk_rust_binding_py as nacos
+
+INSTANCE_COUNT = 5000
+METADATA_COUNT = 10
+ROUNDS = 5
+
+
+def build_instances(count: int) -> nacos.NacosServiceInstanceList:
+    metadata = {"key-%d" % i: "value-%d" % i for i in range(METADATA_COUNT)}
+    return nacos.NacosServiceInstanceList(
+        [
+            nacos.NacosServiceInstance(
+                "10.0.%d.%d" % (i // 256, i % 256), 8080, metadata=metadata
+            )
+            for i in range(count)
+        ]
+    )
+
+
+class InstanceListTest(unittest.TestCase):
+    def setUp(self):
+        self.instances = nacos.NacosServiceInstanceList(
+            [
+                nacos.NacosServiceInstance("10.0.0.1", 8080, weight=1.0),
+                nacos.NacosServiceInstance("10.0.0.2", 8080, cluster_name="a"),
+                nacos.NacosServiceInstance("10.0.0.1", 8080, weight=2.0),
+            ]
+        )
+
+    def test_sequence(self):
+        self.assertEqual(len(self.instances), 3)
+        self.assertEqual(self.instances[-1].weight, 2.0)
+        self.assertEqual(
+            [i.ip for i in self.instances[1:]], ["10.0.0.2", "10.0.0.1"]
+        )
+        self.assertEqual([i.ip for i in reversed(self.instances)][0], "10.0.0.1")
+        with self.assertRaises(IndexError):
+            self.instances[3]
+
+    def test_identity(self):
+        # compared by ip, port and cluster_name, not the other fields
+        same = nacos.NacosServiceInstance("10.0.0.1", 8080, weight=5.0, healthy=False)
+        self.assertIn(same, self.instances)
+        self.assertEqual(self.instances.index(same), 0)
+        self.assertEqual(self.instances.index(same, 1), 2)
+        self.assertEqual(self.instances.count(same), 2)
+
+        # no cluster_name is the cluster DEFAULT
+        self.assertIn(
+            nacos.NacosServiceInstance("10.0.0.1", 8080, cluster_name="DEFAULT"),
+            self.instances,
+        )
+        self.assertNotIn(nacos.NacosServiceInstance("10.0.0.2", 8080), self.instances)
+        self.assertNotIn("10.0.0.1", self.instances)
+        self.assertEqual(self.instances.count(None), 0)
+        with self.assertRaises(ValueError):
+            self.instances.index(nacos.NacosServiceInstance("10.0.0.3", 8080))
+
+    def test_pickle(self):
+        self.assertEqual(pickle.loads(pickle.dumps(self.instances)), self.instances)
+
+
+class InstanceListBenchmark(unittest.TestCase):
+    """`to_list()` converts every instance by `transfer_rust_instance_to_ffi`, which is what
+    get_all_instances and the naming listener did before NacosServiceInstanceList."""
+
+    @classmethod
+    def setUpClass(cls):
+        cls.instances = build_instances(INSTANCE_COUNT)
+
+    def bench(self, name: str, func) -> float:
+        seconds = min(timeit.repeat(func, number=ROUNDS, repeat=3)) / ROUNDS
+        print("\n%-36s %10.3f ms" % (name, seconds * 1000), end=" ")
+        return seconds
+
+    def test_first_instance(self):
+        converted = self.bench(
+            "first instance, converted", lambda: self.instances.to_list()[0]
+        )
+        lazy = self.bench("first instance, lazy", lambda: self.instances[0])
+        self.assertLess(lazy, converted)
+
+    def test_iterate(self):
+        # every instance is created either way, the lazy one keeps no list of them
+        self.bench(
+            "iterate all, converted", lambda: [i.ip for i in self.instances.to_list()]
+        )
+        self.bench("iterate all, lazy", lambda: [i.ip for i in self.instances])
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,139 @@
+"""
+Tests of the process-wide runtime shared by the sync and async clients.
+
+The push and fork tests need a Nacos server, set by env `NACOS_SERVER_ADDR`, default 127.0.0.1:8848,
+it is skipped when the server is unreachable. Run after `maturin develop`:
+
+    python -m unittest tests/test_runtime.py
+"""
+
+import os
+import socket
+import subprocess
+import sys
+import threading
+import unittest
+import uuid
+
+import nacos_sdk_rust_binding_py as nacos
+
+SERVER_ADDR = os.environ.get("NACOS_SERVER_ADDR", "127.0.0.1:8848")
+USERNAME = os.environ.get("NACOS_USERNAME", "nacos")
+PASSWORD = os.environ.get("NACOS_PASSWORD", "nacos")
+PUSH_TIMEOUT_SECONDS = 10
+
+
+def server_reachable() -> bool:
+    host, _, port = SERVER_ADDR.split(",")[0].rpartition(":")
+    try:
+        with socket.create_connection((host, int(port)), timeout=1):
+            return True
+    except OSError:
+        return False
+
+
+def run_python(code: str) -> subprocess.CompletedProcess:
+    return subprocess.run(
+        [sys.executable, "-c", code], capture_output=True, text=True, timeout=60
+    )
+
+
+class InitRuntimeTest(unittest.TestCase):
+    # each in a new process, because the runtime is initialized once per process
+
+    def test_init_runtime_once(self):
+        result = run_python(
+            "import nacos_sdk_rust_binding_py as nacos\n"
+            "nacos.init_runtime(2)\n"
+            "try:\n"
+            "    nacos.init_runtime(2)\n"
+            "except RuntimeError as e:\n"
+            "    print(e)\n"
+        )
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertIn("initialized already", result.stdout)
+
+    def test_init_runtime_invalid_worker_threads(self):
+        result = run_python(
+            "import nacos_sdk_rust_binding_py as nacos\n"
+            "try:\n"
+            "    nacos.init_runtime(0)\n"
+            "except ValueError as e:\n"
+            "    print(e)\n"
+        )
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertIn("worker_threads", result.stdout)
+
+
+@unittest.skipUnless(server_reachable(), "Nacos server %s is unreachable" % SERVER_ADDR)
+class IdlePushTest(unittest.TestCase):
+    def test_push_received_while_python_is_idle(self):
+        data_id = "test-runtime-%s" % uuid.uuid4().hex
+        group = "DEFAULT_GROUP"
+        client = nacos.NacosConfigClient(
+            nacos.ClientOptions(SERVER_ADDR, "", "test_runtime", USERNAME, PASSWORD)
+        )
+        received = threading.Event()
+        client.add_listener(data_id, group, lambda config_resp: received.set())
+
+        # published by another process, this one only sleeps and never calls into the client
+        result = run_python(
+            "import nacos_sdk_rust_binding_py as nacos\n"
+            "client = nacos.NacosConfigClient(nacos.ClientOptions(%r, '', 'test_runtime', %r, %r))\n"
+            "client.publish_config(%r, %r, 'pushed')\n"
+            % (SERVER_ADDR, USERNAME, PASSWORD, data_id, group)
+        )
+        self.assertEqual(result.returncode, 0, result.stderr)
+        try:
+            self.assertTrue(
+                received.wait(PUSH_TIMEOUT_SECONDS), "the push is not received while idle"
+            )
+        finally:
+            client.remove_config(data_id, group)
+
+
+@unittest.skipUnless(server_reachable(), "Nacos server %s is unreachable" % SERVER_ADDR)
+class ForkTest(unittest.TestCase):
+    def test_fork_after_client_built(self):
+        data_id = "test-runtime-fork-%s" % uuid.uuid4().hex
+        group = "DEFAULT_GROUP"
+        # forked in another process, so that this one keeps a single thread at fork
+        result = run_python(
+            "import asyncio, os, sys\n"
+            "import nacos_sdk_rust_binding_py as nacos\n"
+            "options = nacos.ClientOptions(%r, '', 'test_runtime', %r, %r)\n"
+            "parent = nacos.NacosConfigClient(options)\n"
+            "parent.publish_config(%r, %r, 'before fork')\n"
+            "sys.stdout.flush()\n"
+            "pid = os.fork()\n"
+            "if pid == 0:\n"
+            "    try:\n"
+            "        parent.get_config(%r, %r)\n"
+            "        print('inherited client works')\n"
+            "    except RuntimeError as e:\n"
+            "        print('inherited client raises', e)\n"
+            "    nacos.init_runtime(2)\n"
+            "    child = nacos.NacosConfigClient(options)\n"
+            "    print('child client gets', child.get_config(%r, %r))\n"
+            "    async def main():\n"
+            "        client = nacos.AsyncNacosConfigClient(options)\n"
+            "        return await client.get_config(%r, %r)\n"
+            "    print('child async client gets', asyncio.run(main()))\n"
+            "    sys.stdout.flush()\n"
+            "    os._exit(0)\n"
+            "_, status = os.waitpid(pid, 0)\n"
+            "print('child exits', os.waitstatus_to_exitcode(status))\n"
+            "print('parent client gets', parent.get_config(%r, %r))\n"
+            "parent.remove_config(%r, %r)\n"
+            % ((SERVER_ADDR, USERNAME, PASSWORD) + (data_id, group) * 6)
+        )
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertIn("inherited client raises", result.stdout)
+        self.assertIn("child client gets before fork", result.stdout)
+        self.assertIn("child async client gets before fork", result.stdout)
+        self.assertIn("child exits 0", result.stdout)
+        self.assertIn("parent client gets before fork", result.stdout)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,134 @@
+"""
+Tests of the TLS of the gRPC connection, offline: a CA is generated locally by `openssl`, and a
+TLS stand-in server records the handshakes. TLS is enabled by the build feature `tls`, they are
+skipped without it. Run after `maturin develop --features tls`:
+
+    python -m unittest tests/test_tls.py
+"""
+
+import os
+import shutil
+import socket
+import ssl
+import subprocess
+import sys
+import tempfile
+import threading
+import time
+import unittest
+from typing import Optional
+
+import nacos_sdk_rust_binding_py as nacos
+
+HANDSHAKE_TIMEOUT_SECONDS = 10
+
+
+def openssl(*args: str, cwd: str):
+    subprocess.run(["openssl", *args], cwd=cwd, check=True, capture_output=True)
+
+
+def generate_certs(cert_dir: str):
+    """CA, and the server cert of 127.0.0.1 signed by the CA."""
+    openssl("req", "-x509", "-newkey", "rsa:2048", "-nodes", "-days", "1",
+            "-subj", "/CN=nacos-test-ca", "-keyout", "ca.key", "-out", "ca.pem", cwd=cert_dir)
+    with open(os.path.join(cert_dir, "server.ext"), "w") as ext:
+        ext.write("subjectAltName=IP:127.0.0.1\n")
+    openssl("req", "-newkey", "rsa:2048", "-nodes", "-subj", "/CN=nacos-server",
+            "-keyout", "server.key", "-out", "server.csr", cwd=cert_dir)
+    openssl("x509", "-req", "-in", "server.csr", "-CA", "ca.pem", "-CAkey", "ca.key",
+            "-CAcreateserial", "-days", "1", "-out", "server.pem", "-extfile", "server.ext",
+            cwd=cert_dir)
+
+
+class TlsStandInServer:
+    """Accepts TLS connections and records each handshake, it does not speak gRPC."""
+
+    def __init__(self, cert_dir: str):
+        self.context = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+        self.context.load_cert_chain(
+            os.path.join(cert_dir, "server.pem"), os.path.join(cert_dir, "server.key")
+        )
+        self.context.set_alpn_protocols(["h2"])
+        self.failures = []
+        self.completed = threading.Event()
+        self.failed = threading.Event()
+        self.sock = socket.create_server(("127.0.0.1", 0))
+        self.port = self.sock.getsockname()[1]
+        threading.Thread(target=self._serve, daemon=True).start()
+
+    def _serve(self):
+        while True:
+            try:
+                conn, _ = self.sock.accept()
+            except OSError:
+                return
+            try:
+                with self.context.wrap_socket(conn, server_side=True):
+                    self.completed.set()
+            except (ssl.SSLError, OSError) as e:
+                self.failures.append(e)
+                self.failed.set()
+
+    def close(self):
+        self.sock.close()
+
+
+@unittest.skipUnless(nacos.TLS_ENABLED, "built without the feature `tls`")
+@unittest.skipUnless(shutil.which("openssl"), "openssl is not found")
+class TlsHandshakeTest(unittest.TestCase):
+    @classmethod
+    def setUpClass(cls):
+        cls.cert_dir = tempfile.mkdtemp()
+        generate_certs(cls.cert_dir)
+
+    @classmethod
+    def tearDownClass(cls):
+        shutil.rmtree(cls.cert_dir, ignore_errors=True)
+
+    def cert(self, name: str) -> str:
+        return os.path.join(self.cert_dir, name)
+
+    def connect(self, server: TlsStandInServer, ca_cert_path: Optional[str]):
+        """Build a client in another process, which is killed after the first handshake."""
+        env = dict(os.environ)
+        env.pop("NACOS_CLIENT_TLS_CA_CERT", None)
+        if ca_cert_path:
+            env["NACOS_CLIENT_TLS_CA_CERT"] = ca_cert_path
+        code = (
+            "import os, threading, time\n"
+            "import nacos_sdk_rust_binding_py as nacos\n"
+            "options = nacos.ClientOptions('127.0.0.1:8848', '', 'test_tls', grpc_port=%d)\n"
+            "threading.Thread(target=lambda: nacos.NacosConfigClient(options), daemon=True).start()\n"
+            "time.sleep(%d)\n"
+            "os._exit(0)\n" % (server.port, HANDSHAKE_TIMEOUT_SECONDS)
+        )
+        process = subprocess.Popen([sys.executable, "-c", code], env=env)
+        deadline = time.monotonic() + HANDSHAKE_TIMEOUT_SECONDS
+        while time.monotonic() < deadline and process.poll() is None:
+            if server.completed.is_set() or server.failed.is_set():
+                break
+            time.sleep(0.1)
+        process.kill()
+        process.wait()
+
+    def test_tls_with_ca_cert(self):
+        server = TlsStandInServer(self.cert_dir)
+        try:
+            self.connect(server, self.cert("ca.pem"))
+            self.assertTrue(server.completed.is_set(), server.failures)
+        finally:
+            server.close()
+
+    def test_untrusted_server_cert_rejected(self):
+        server = TlsStandInServer(self.cert_dir)
+        try:
+            # verified by the system roots, which do not have the local CA
+            self.connect(server, None)
+            self.assertTrue(server.failed.is_set())
+            self.assertFalse(server.completed.is_set())
+        finally:
+            server.close()
+
+
+if __name__ == "__main__":
+    unittest.main()